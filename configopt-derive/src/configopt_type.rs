@@ -2,17 +2,28 @@ pub mod generate;
 pub mod parse;
 
 use generate::default_config_files::Attribute as DefaultConfigFilesAttribute;
-use parse::{CasingStyle, ParsedField, ParsedVariant};
+use parse::{CasingStyle, ConfigVersion, ParsedField, ParsedVariant};
 use proc_macro2::TokenStream;
 use proc_macro_roids::DeriveInputExt;
 use quote::quote;
 use syn::{parse_quote, punctuated::Punctuated, Data, DeriveInput, Fields, Ident, Token};
 
 pub enum ConfigOptConstruct {
-    Struct(Ident, Option<DefaultConfigFilesAttribute>, Vec<ParsedField>),
+    Struct(StructConstruct),
     Enum(Ident, Vec<ParsedVariant>),
 }
 
+/// The type level `configopt` attributes and parsed fields of a struct
+pub struct StructConstruct {
+    pub ident: Ident,
+    pub default_config_file: Option<DefaultConfigFilesAttribute>,
+    pub interpolate_env: bool,
+    pub annotate_help: bool,
+    pub config_command: bool,
+    pub config_version: Option<ConfigVersion>,
+    pub parsed_fields: Vec<ParsedField>,
+}
+
 impl ConfigOptConstruct {
     pub fn convert_and_parse(original_type: DeriveInput) -> (DeriveInput, ConfigOptConstruct) {
         let ident = original_type.ident.clone();
@@ -21,11 +32,27 @@ impl ConfigOptConstruct {
         // Change the ident to a configopt ident
         configopt_type.ident = parse::configopt_ident(&configopt_type.ident);
 
+        // Check if config files are versioned, this must happen first as `upgrade` is not valid
+        // meta syntax and hides the other attributes until it is trimmed
+        let config_version = parse::config_version(&mut configopt_type.attrs);
+
         // Check if we have a default config file
         let default_config_file = configopt_type
             .tag_parameter(&parse_quote!(configopt), &parse_quote!(default_config_file))
             .map(|a| a.into());
 
+        // Check if all fields should have environment variables interpolated
+        let interpolate_env = configopt_type
+            .contains_tag(&parse_quote!(configopt), &parse_quote!(interpolate_env));
+
+        // Check if `--help` should show where the current values came from
+        let annotate_help = configopt_type
+            .contains_tag(&parse_quote!(configopt), &parse_quote!(annotate_help));
+
+        // Check if the `config` subcommand should be handled
+        let config_command = configopt_type
+            .contains_tag(&parse_quote!(configopt), &parse_quote!(config_command));
+
         // Get a list of attributes to retain on the configopt type
         let mut retained_attrs = configopt_type
             .tag_parameters(&parse_quote!(configopt), &parse_quote!(attrs))
@@ -77,7 +104,15 @@ impl ConfigOptConstruct {
                                 )
                             })
                             .collect::<Vec<_>>();
-                        ConfigOptConstruct::Struct(ident, default_config_file, parsed_fields)
+                        ConfigOptConstruct::Struct(StructConstruct {
+                            ident,
+                            default_config_file,
+                            interpolate_env,
+                            annotate_help,
+                            config_command,
+                            config_version,
+                            parsed_fields,
+                        })
                     }
                     Fields::Unnamed(_) => {
                         panic!("`ConfigOpt` cannot be derived for unnamed struct")
@@ -86,6 +121,9 @@ impl ConfigOptConstruct {
                 }
             }
             Data::Enum(data) => {
+                if config_version.is_some() {
+                    panic!("`#[configopt(version = ..)]` can only be used on structs");
+                }
                 let mut parsed_variants = Vec::new();
                 for variant in &mut data.variants {
                     parse::retain_attrs(&mut variant.attrs, &retained_attrs);
@@ -117,30 +155,79 @@ impl ConfigOptConstruct {
         let other = parse_quote! {other};
         let configopt_ident = parse::configopt_ident(ident);
         match self {
-            Self::Struct(_, default_config_file, parsed_fields) => {
+            Self::Struct(StructConstruct {
+                default_config_file,
+                interpolate_env,
+                annotate_help,
+                config_command,
+                config_version,
+                parsed_fields,
+                ..
+            }) => {
                 use generate::core::struct_type;
 
-                let configopt_patch = struct_type::patch(&parsed_fields);
-                let configopt_take = struct_type::take(&parsed_fields);
-                let configopt_patch_for = struct_type::patch_for(&parsed_fields);
-                let configopt_take_for = struct_type::take_for(&parsed_fields);
-                let configopt_is_empty = struct_type::is_empty(&parsed_fields);
-                let configopt_is_complete = struct_type::is_complete(&parsed_fields);
-                let configopt_is_convertible = struct_type::is_convertible(&parsed_fields);
-                let configopt_from = struct_type::from(&parsed_fields, &other);
-                let configopt_try_from = struct_type::try_from(&parsed_fields);
+                let configopt_patch = struct_type::patch(parsed_fields);
+                let configopt_take = struct_type::take(parsed_fields);
+                let configopt_patch_for = struct_type::patch_for(parsed_fields);
+                let configopt_take_for = struct_type::take_for(parsed_fields);
+                let configopt_diff = struct_type::diff(parsed_fields);
+                let configopt_diff_resolved = struct_type::diff_resolved(parsed_fields);
+                let configopt_is_empty = struct_type::is_empty(parsed_fields);
+                let configopt_is_complete = struct_type::is_complete(parsed_fields);
+                let configopt_is_convertible = struct_type::is_convertible(parsed_fields);
+                let configopt_from = struct_type::from(parsed_fields, &other);
+                let configopt_try_from = struct_type::try_from(parsed_fields);
                 let default_config_files =
                     generate::default_config_files::generate(default_config_file.as_ref());
+                let config_command_files = if *config_command {
+                    quote! {Some(Self::default_config_files())}
+                } else {
+                    quote! {None}
+                };
                 let handle_config_files_generate =
                     generate::handle_config_files::generate_for_struct(parsed_fields.as_slice());
                 let handle_config_files_patch = generate::handle_config_files::patch_for_struct(
                     parsed_fields.as_slice(),
                     &configopt_ident,
                 );
+                let handle_config_files_paths = generate::handle_config_files::paths_for_struct(
+                    parsed_fields.as_slice(),
+                    &configopt_ident,
+                );
+                let handle_config_files_check_requested =
+                    generate::handle_config_files::check_requested_for_struct(
+                        parsed_fields.as_slice(),
+                    );
+                let handle_config_files_explain_requested =
+                    generate::handle_config_files::explain_requested_for_struct(
+                        parsed_fields.as_slice(),
+                    );
+                let handle_config_files_check =
+                    generate::handle_config_files::check_for_struct(parsed_fields.as_slice());
+                let handle_config_files_arg =
+                    generate::handle_config_files::config_files_arg_for_struct(
+                        parsed_fields.as_slice(),
+                    );
+                let validate = generate::validate::for_struct(parsed_fields.as_slice());
+                let constraint_args = generate::constraints::for_struct(parsed_fields.as_slice());
                 let toml_config_generator_with_prefix =
-                    generate::toml_config::for_struct(&parsed_fields);
+                    generate::toml_config::for_struct(parsed_fields);
                 let configopt_defaults_field_match =
-                    generate::configopt_defaults::for_struct(&parsed_fields);
+                    generate::configopt_defaults::for_struct(parsed_fields);
+                let process_config_value =
+                    generate::config_value::for_struct(parsed_fields, *interpolate_env);
+                let rename_aliases =
+                    generate::config_value::rename_aliases_for_struct(parsed_fields);
+                let config_version_number =
+                    generate::config_value::config_version(config_version.as_ref());
+                let upgrade_config_table =
+                    generate::config_value::upgrade_for_struct(config_version.as_ref());
+                let nest_subcommand_tables =
+                    generate::config_value::nest_subcommands_for_struct(parsed_fields);
+                let json_schema_properties = generate::json_schema::for_struct(parsed_fields);
+                let reference_sections = generate::reference::for_struct(parsed_fields);
+                let get_with_path = generate::dotted_key::get_for_struct(parsed_fields);
+                let set_with_path = generate::dotted_key::set_for_struct(parsed_fields);
                 quote! {
                     #lints
                     impl #configopt_ident {
@@ -164,6 +251,23 @@ impl ConfigOptConstruct {
                             #configopt_patch_for
                         }
 
+                        /// Compute the changes from `self` to `other`
+                        pub fn diff(&self, other: &#configopt_ident) -> ::configopt::ConfigDiff {
+                            let mut changes = Vec::new();
+                            self.diff_with_prefix(other, &[], &mut changes);
+                            ::configopt::ConfigDiff(changes)
+                        }
+
+                        #[doc(hidden)]
+                        pub fn diff_with_prefix(
+                            &self,
+                            other: &#configopt_ident,
+                            prefix: &[String],
+                            changes: &mut Vec<::configopt::ConfigChange>,
+                        ) {
+                            #configopt_diff
+                        }
+
                         /// Check if all fields of `self` are `None`
                         #[allow(clippy::eq_op)]
                         pub fn is_empty(&self) -> bool {
@@ -182,6 +286,114 @@ impl ConfigOptConstruct {
                             #configopt_is_convertible
                         }
 
+                        /// Parse `self` from a config document
+                        ///
+                        /// The document is read the same as a config file. It has no directory so
+                        /// relative paths are left relative to the current directory.
+                        pub fn from_config_str(format: ::configopt::ConfigFormat, contents: &str) -> ::configopt::Result<Self> {
+                            let mut value: ::toml::Value = ::configopt::from_config_str(format, contents)?;
+                            #configopt_ident::process_config_value(&mut value, ::std::path::Path::new(""))?;
+                            if let ::toml::Value::Table(table) = &mut value {
+                                #configopt_ident::nest_subcommand_tables(table);
+                            }
+                            ::configopt::from_config_value(value)
+                        }
+
+                        #[doc(hidden)]
+                        pub fn process_config_value_with_prefix(
+                            table: &mut ::toml::value::Table,
+                            path: &::std::path::Path,
+                            serde_prefix: &[String],
+                            interpolate_all: bool,
+                        ) -> ::configopt::Result<()> {
+                            #process_config_value
+                        }
+
+                        #[doc(hidden)]
+                        pub fn nest_subcommand_tables(table: &mut ::toml::value::Table) {
+                            #nest_subcommand_tables
+                        }
+
+                        /// Read the config file at `path` with its values interpolated and resolved
+                        #[doc(hidden)]
+                        pub fn read_config_value(path: &::std::path::Path) -> ::configopt::Result<::toml::Value> {
+                            let mut value: ::toml::Value = ::configopt::from_toml_file(path)?;
+                            #configopt_ident::process_config_value(&mut value, path)?;
+                            Ok(value)
+                        }
+
+                        /// Upgrade, rename and interpolate the values of a config document read
+                        /// from `path`
+                        #[doc(hidden)]
+                        pub fn process_config_value(
+                            value: &mut ::toml::Value,
+                            path: &::std::path::Path,
+                        ) -> ::configopt::Result<()> {
+                            if let ::toml::Value::Table(table) = value {
+                                <#configopt_ident as ::configopt::ConfigOptType>::upgrade_config_table(table)
+                                    .map_err(|e| ::configopt::Error::UpgradeConfig(path.to_path_buf(), e))?;
+                                let mut deprecated = Vec::new();
+                                <#configopt_ident as ::configopt::ConfigOptType>::rename_aliases_with_prefix(
+                                    table,
+                                    &[],
+                                    &[],
+                                    &mut deprecated,
+                                );
+                                ::configopt::record_deprecated(path, &deprecated);
+                                #configopt_ident::process_config_value_with_prefix(table, path, &[], false)?;
+                            }
+                            Ok(())
+                        }
+
+                        /// Get the JSON Schema of the config file
+                        pub fn json_schema() -> ::configopt::json_schema::Value {
+                            let mut properties = ::configopt::json_schema::Map::new();
+                            let mut definitions = ::configopt::json_schema::Map::new();
+                            Self::json_schema_properties(&mut properties, &mut definitions);
+                            ::configopt::json_schema::document(&#ident::clap(), properties, definitions)
+                        }
+
+                        #[doc(hidden)]
+                        pub fn json_schema_properties(
+                            properties: &mut ::configopt::json_schema::Map<String, ::configopt::json_schema::Value>,
+                            definitions: &mut ::configopt::json_schema::Map<String, ::configopt::json_schema::Value>,
+                        ) {
+                            let app = &#ident::clap();
+                            #json_schema_properties
+                        }
+
+                        /// Get a reference document of every config key
+                        pub fn reference() -> ::configopt::reference::Reference {
+                            let app = #ident::clap();
+                            let mut sections = Vec::new();
+                            Self::reference_sections(
+                                &app,
+                                &[],
+                                ::configopt::reference::Section::command(&app, None),
+                                &mut sections,
+                            );
+                            ::configopt::reference::Reference::new(&app, sections)
+                        }
+
+                        #[doc(hidden)]
+                        pub fn reference_sections(
+                            app: &::structopt::clap::App,
+                            serde_prefix: &[String],
+                            mut section: ::configopt::reference::Section,
+                            sections: &mut Vec<::configopt::reference::Section>,
+                        ) {
+                            #reference_sections
+                        }
+
+                        /// Render a roff man page documenting the CLI, the config file keys and the default config files
+                        pub fn man_page() -> String {
+                            ::configopt::man_page(
+                                &#ident::clap(),
+                                &Self::reference(),
+                                &Self::default_config_files(),
+                            )
+                        }
+
                         #default_config_files
                     }
 
@@ -210,7 +422,11 @@ impl ConfigOptConstruct {
                         type Error = ::configopt::Error;
 
                         fn try_from(path: &::std::path::Path) -> ::std::result::Result<Self, Self::Error> {
-                            ::configopt::from_toml_file(path)
+                            let mut value = #configopt_ident::read_config_value(path)?;
+                            if let ::toml::Value::Table(table) = &mut value {
+                                #configopt_ident::nest_subcommand_tables(table);
+                            }
+                            ::configopt::from_toml_value(path, value)
                         }
                     }
 
@@ -257,14 +473,105 @@ impl ConfigOptConstruct {
                             None
                         }
 
-                        fn patch_with_config_files(&mut self) -> ::configopt::Result<&mut #configopt_ident> {
+                        fn patch_with_config_files_recording(
+                            &mut self,
+                            files: &mut Vec<(::std::path::PathBuf, Self)>,
+                        ) -> ::configopt::Result<&mut #configopt_ident> {
                             #handle_config_files_patch
                         }
 
+                        fn config_file_paths(&self) -> Vec<::std::path::PathBuf> {
+                            #handle_config_files_paths
+                        }
+
+                        fn annotate_help() -> bool {
+                            #annotate_help
+                        }
+
+                        fn config_command_files() -> Option<Vec<::std::path::PathBuf>> {
+                            #config_command_files
+                        }
+
+                        fn config_files_arg_name() -> Option<&'static str> {
+                            #handle_config_files_arg
+                        }
+
+                        fn config_keys() -> Vec<String> {
+                            #configopt_ident::reference()
+                                .sections
+                                .into_iter()
+                                .flat_map(|section| section.entries)
+                                .map(|entry| entry.key)
+                                .collect()
+                        }
+
+                        fn check_config_requested(&self) -> bool {
+                            #handle_config_files_check_requested
+                        }
+
+                        fn explain_config_requested(&self) -> bool {
+                            #handle_config_files_explain_requested
+                        }
+
+                        fn check_config_files(&mut self, problems: &mut Vec<String>) {
+                            #handle_config_files_check
+                        }
+
+                        fn validate_with_prefix(&self, prefix: &[String], failures: &mut Vec<(String, String)>) {
+                            #validate
+                        }
+
+                        fn rename_aliases_with_prefix(
+                            table: &mut ::toml::value::Table,
+                            prefix: &[String],
+                            written_prefix: &[String],
+                            deprecated: &mut Vec<::configopt::DeprecatedKey>,
+                        ) {
+                            #rename_aliases
+                        }
+
+                        fn config_version() -> Option<u32> {
+                            #config_version_number
+                        }
+
+                        fn upgrade_config_table(
+                            table: &mut ::toml::value::Table,
+                        ) -> ::std::result::Result<Option<u32>, String> {
+                            #upgrade_config_table
+                        }
+
+                        fn process_config_document(
+                            value: &mut ::toml::Value,
+                            path: &::std::path::Path,
+                        ) -> ::configopt::Result<()> {
+                            #configopt_ident::process_config_value(value, path)
+                        }
+
+                        fn constraint_args_with_prefix(
+                            &self,
+                            prefix: &[String],
+                            group: &mut Vec<::configopt::constraints::ConstraintArg>,
+                            groups: &mut Vec<Vec<::configopt::constraints::ConstraintArg>>,
+                        ) {
+                            #constraint_args
+                        }
+
                         fn toml_config_with_prefix(&self, serde_prefix: &[String]) -> String {
                             let app = #ident::clap();
                             #toml_config_generator_with_prefix
                         }
+
+                        fn get_with_path(&self, path: &[&str]) -> Option<Option<::toml::Value>> {
+                            #get_with_path
+                        }
+
+                        fn set_with_path(
+                            &mut self,
+                            path: &[&str],
+                            value: ::toml::Value,
+                        ) -> Option<::std::result::Result<(), String>> {
+                            #set_with_path
+                        }
                     }
 
                     #lints
@@ -278,27 +585,55 @@ impl ConfigOptConstruct {
                         fn take(&mut self, other: &mut Self::ConfigOptType) {
                             other.take_for(self);
                         }
+
+                        fn diff_with_prefix(
+                            &self,
+                            other: &Self,
+                            prefix: &[String],
+                            changes: &mut Vec<::configopt::ConfigChange>,
+                        ) {
+                            #configopt_diff_resolved
+                        }
                     }
                 }
             }
             Self::Enum(_, parsed_variants) => {
                 use generate::core::enum_type;
 
-                let configopt_patch = enum_type::patch(&parsed_variants);
-                let configopt_take = enum_type::take(&parsed_variants);
-                let configopt_patch_for = enum_type::patch_for(&parsed_variants);
-                let configopt_take_for = enum_type::take_for(&parsed_variants);
-                let configopt_is_empty = enum_type::is_empty(&parsed_variants);
-                let configopt_is_complete = enum_type::is_complete(&parsed_variants);
-                let configopt_is_convertible = enum_type::is_convertible(&parsed_variants);
-                let configopt_from = enum_type::from(&parsed_variants);
-                let configopt_try_from = enum_type::try_from(&parsed_variants);
+                let configopt_patch = enum_type::patch(parsed_variants);
+                let configopt_take = enum_type::take(parsed_variants);
+                let configopt_patch_for = enum_type::patch_for(parsed_variants);
+                let configopt_take_for = enum_type::take_for(parsed_variants);
+                let configopt_diff = enum_type::diff(parsed_variants, false);
+                let configopt_diff_resolved = enum_type::diff(parsed_variants, true);
+                let configopt_variant_name = enum_type::variant_name(parsed_variants, false);
+                let configopt_variant_name_resolved =
+                    enum_type::variant_name(parsed_variants, true);
+                let configopt_is_empty = enum_type::is_empty(parsed_variants);
+                let configopt_is_complete = enum_type::is_complete(parsed_variants);
+                let configopt_is_convertible = enum_type::is_convertible(parsed_variants);
+                let configopt_from = enum_type::from(parsed_variants);
+                let configopt_try_from = enum_type::try_from(parsed_variants);
                 let handle_config_files_generate =
                     generate::handle_config_files::generate_for_enum(parsed_variants);
                 let handle_config_files_patch =
                     generate::handle_config_files::patch_for_enum(parsed_variants);
+                let handle_config_files_paths =
+                    generate::handle_config_files::paths_for_enum(parsed_variants);
+                let handle_config_files_check =
+                    generate::handle_config_files::check_for_enum(parsed_variants);
+                let validate = generate::validate::for_enum(parsed_variants);
+                let constraint_args = generate::constraints::for_enum(parsed_variants);
                 let configopt_defaults_variant =
-                    generate::configopt_defaults::for_enum(&parsed_variants);
+                    generate::configopt_defaults::for_enum(parsed_variants);
+                let json_schema_subcommands = generate::json_schema::for_enum(parsed_variants);
+                let reference_subcommands = generate::reference::for_enum(parsed_variants);
+                let get_subcommand_with_path = generate::dotted_key::get_for_enum(parsed_variants);
+                let set_subcommand_with_path = generate::dotted_key::set_for_enum(parsed_variants);
+                let set_with_path = generate::dotted_key::set_for_enum_self(parsed_variants);
+                let process_config_value = generate::config_value::for_enum(parsed_variants);
+                let nest_subcommand_tables =
+                    generate::config_value::nest_subcommands_for_enum(parsed_variants);
 
                 quote! {
 
@@ -336,6 +671,79 @@ impl ConfigOptConstruct {
                             }
                         }
 
+                        #[doc(hidden)]
+                        pub fn diff_with_prefix(
+                            &self,
+                            other: &#configopt_ident,
+                            prefix: &[String],
+                            changes: &mut Vec<::configopt::ConfigChange>,
+                        ) {
+                            match (self, other) {
+                                #configopt_diff
+                                _ => {}
+                            }
+                        }
+
+                        #[doc(hidden)]
+                        pub fn json_schema_subcommands(
+                            properties: &mut ::configopt::json_schema::Map<String, ::configopt::json_schema::Value>,
+                            definitions: &mut ::configopt::json_schema::Map<String, ::configopt::json_schema::Value>,
+                        ) {
+                            let app = &#ident::clap();
+                            #json_schema_subcommands
+                        }
+
+                        #[doc(hidden)]
+                        pub fn reference_subcommands(
+                            app: &::structopt::clap::App,
+                            command: &str,
+                            serde_prefix: &[String],
+                            sections: &mut Vec<::configopt::reference::Section>,
+                        ) {
+                            #reference_subcommands
+                        }
+
+                        #[doc(hidden)]
+                        pub fn process_subcommand_config_value_with_prefix(
+                            table: &mut ::toml::value::Table,
+                            path: &::std::path::Path,
+                            serde_prefix: &[String],
+                            interpolate_all: bool,
+                        ) -> ::configopt::Result<()> {
+                            #process_config_value
+                        }
+
+                        #[doc(hidden)]
+                        pub fn nest_subcommand_tables(table: &mut ::toml::value::Table, field: &str) {
+                            #nest_subcommand_tables
+                        }
+
+                        #[doc(hidden)]
+                        #[allow(unreachable_patterns)]
+                        pub fn get_subcommand_with_path(
+                            this: Option<&Self>,
+                            path: &[&str],
+                        ) -> Option<Option<::toml::Value>> {
+                            #get_subcommand_with_path
+                        }
+
+                        #[doc(hidden)]
+                        #[allow(unreachable_patterns)]
+                        pub fn set_subcommand_with_path(
+                            this: &mut Option<Self>,
+                            path: &[&str],
+                            value: ::toml::Value,
+                        ) -> Option<::std::result::Result<(), String>> {
+                            #set_subcommand_with_path
+                        }
+
+                        #[doc(hidden)]
+                        pub fn variant_name(&self) -> Option<&'static str> {
+                            match self {
+                                #configopt_variant_name
+                            }
+                        }
+
                         /// Check if all fields of `self` are `None` applied recursively
                         #[allow(clippy::eq_op)]
                         pub fn is_empty(&self) -> bool {
@@ -409,7 +817,10 @@ impl ConfigOptConstruct {
                         }
 
 
-                        fn patch_with_config_files(&mut self) -> ::configopt::Result<&mut #configopt_ident> {
+                        fn patch_with_config_files_recording(
+                            &mut self,
+                            _files: &mut Vec<(::std::path::PathBuf, Self)>,
+                        ) -> ::configopt::Result<&mut #configopt_ident> {
                             match self {
                                 #handle_config_files_patch
                                 _ => {}
@@ -417,9 +828,50 @@ impl ConfigOptConstruct {
                             Ok(self)
                         }
 
+                        fn config_file_paths(&self) -> Vec<::std::path::PathBuf> {
+                            match self {
+                                #handle_config_files_paths
+                                _ => Vec::new(),
+                            }
+                        }
+
+                        fn check_config_files(&mut self, problems: &mut Vec<String>) {
+                            match self {
+                                #handle_config_files_check
+                                _ => {}
+                            }
+                        }
+
+                        #[allow(unreachable_patterns)]
+                        fn validate_with_prefix(&self, prefix: &[String], failures: &mut Vec<(String, String)>) {
+                            #validate
+                        }
+
+                        #[allow(unreachable_patterns)]
+                        fn constraint_args_with_prefix(
+                            &self,
+                            prefix: &[String],
+                            group: &mut Vec<::configopt::constraints::ConstraintArg>,
+                            groups: &mut Vec<Vec<::configopt::constraints::ConstraintArg>>,
+                        ) {
+                            #constraint_args
+                        }
+
                         fn toml_config_with_prefix(&self, serde_prefix: &[String]) -> String {
                             todo!()
                         }
+
+                        fn get_with_path(&self, path: &[&str]) -> Option<Option<::toml::Value>> {
+                            Self::get_subcommand_with_path(Some(self), path)
+                        }
+
+                        fn set_with_path(
+                            &mut self,
+                            path: &[&str],
+                            value: ::toml::Value,
+                        ) -> Option<::std::result::Result<(), String>> {
+                            #set_with_path
+                        }
                     }
 
                     #lints
@@ -433,6 +885,24 @@ impl ConfigOptConstruct {
                         fn take(&mut self, other: &mut Self::ConfigOptType) {
                             other.take_for(self);
                         }
+
+                        fn diff_with_prefix(
+                            &self,
+                            other: &Self,
+                            prefix: &[String],
+                            changes: &mut Vec<::configopt::ConfigChange>,
+                        ) {
+                            match (self, other) {
+                                #configopt_diff_resolved
+                                _ => {}
+                            }
+                        }
+
+                        fn variant_name(&self) -> Option<&'static str> {
+                            match self {
+                                #configopt_variant_name_resolved
+                            }
+                        }
                     }
                 }
             }
@@ -441,7 +911,7 @@ impl ConfigOptConstruct {
 
     fn ident(&self) -> &Ident {
         match self {
-            Self::Struct(ident, _, _) => ident,
+            Self::Struct(StructConstruct { ident, .. }) => ident,
             Self::Enum(ident, _) => ident,
         }
     }
@@ -1,8 +1,14 @@
 pub mod configopt_defaults;
+pub mod constraints;
 pub mod core;
+pub mod config_value;
 pub mod default_config_files;
+pub mod dotted_key;
 pub mod handle_config_files;
+pub mod json_schema;
+pub mod reference;
 pub mod toml_config;
+pub mod validate;
 mod try_from_error;
 
 pub use try_from_error::generate as try_from_error;
@@ -0,0 +1,307 @@
+use crate::configopt_type::parse::{self, ConfigVersion, FieldType, ParsedField, ParsedVariant};
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+
+// Process the raw value tree of a config file before it is deserialized. This interpolates
+// environment variables and resolves paths relative to the config file.
+fn for_fields(fields: &[ParsedField], interpolate_struct: bool) -> TokenStream {
+    let field_tokens = fields.iter().map(|field| {
+        let span = field.span();
+        let serde_name = field.serde_name();
+        let interpolate_field = interpolate_struct || field.interpolate_env();
+        if !field.in_config_files() {
+            quote! {}
+        } else if field.is_subcommand() {
+            // The keys of a subcommand are nested under its name on the command line
+            let configopt_ty = field.configopt_ty();
+            quote_spanned! {span=>
+                <#configopt_ty>::process_subcommand_config_value_with_prefix(
+                    table,
+                    path,
+                    serde_prefix,
+                    #interpolate_field || interpolate_all,
+                )?;
+            }
+        } else if field.is_structopt_flatten() && field.is_serde_flatten() {
+            // The fields of a flattened struct live in the same table
+            let configopt_ty = field.configopt_ty();
+            quote_spanned! {span=>
+                <#configopt_ty>::process_config_value_with_prefix(
+                    table,
+                    path,
+                    serde_prefix,
+                    #interpolate_field || interpolate_all,
+                )?;
+            }
+        } else if field.is_structopt_flatten() {
+            let configopt_ty = field.configopt_ty();
+            quote_spanned! {span=>
+                if let Some(::toml::Value::Table(table)) = table.get_mut(#serde_name) {
+                    let mut serde_prefix = serde_prefix.to_vec();
+                    serde_prefix.push(String::from(#serde_name));
+                    <#configopt_ty>::process_config_value_with_prefix(
+                        table,
+                        path,
+                        &serde_prefix,
+                        #interpolate_field || interpolate_all,
+                    )?;
+                }
+            }
+        } else {
+            let resolve_path = if field.relative_to_config() {
+                let expand_tilde = field.expand_tilde();
+                quote_spanned! {span=>
+                    ::configopt::resolve_relative_to_config(value, path, #expand_tilde);
+                }
+            } else {
+                quote! {}
+            };
+            quote_spanned! {span=>
+                if let Some(value) = table.get_mut(#serde_name) {
+                    if #interpolate_field || interpolate_all {
+                        let key = if serde_prefix.is_empty() {
+                            String::from(#serde_name)
+                        } else {
+                            format!("{}.{}", serde_prefix.join("."), #serde_name)
+                        };
+                        ::configopt::interpolate_env(value, path, &key)?;
+                    }
+                    #resolve_path
+                }
+            }
+        }
+    });
+    quote! {
+        #(#field_tokens)*
+    }
+}
+
+pub fn for_struct(fields: &[ParsedField], interpolate_struct: bool) -> TokenStream {
+    let fields = for_fields(fields, interpolate_struct);
+    quote! {
+        #fields
+        Ok(())
+    }
+}
+
+// Only the subcommand which is set has a table
+pub fn for_enum(variants: &[ParsedVariant]) -> TokenStream {
+    let variant_tokens = variants.iter().map(|variant| {
+        let structopt_name = variant.structopt_name();
+        let process = match variant.field_type() {
+            FieldType::Unnamed => {
+                let configopt_ty = variant
+                    .unnamed_configopt_ty()
+                    .expect("unnamed variant to have a type");
+                quote! {
+                    <#configopt_ty>::process_config_value_with_prefix(
+                        table,
+                        path,
+                        serde_prefix,
+                        interpolate_all,
+                    )?;
+                }
+            }
+            FieldType::Named(fields) => for_fields(fields, false),
+            FieldType::Unit => return quote! {},
+        };
+        quote! {
+            if let Some(::toml::Value::Table(table)) = table.get_mut(#structopt_name) {
+                let mut serde_prefix = serde_prefix.to_vec();
+                serde_prefix.push(String::from(#structopt_name));
+                let serde_prefix = serde_prefix.as_slice();
+                #process
+            }
+        }
+    });
+    quote! {
+        #(#variant_tokens)*
+        Ok(())
+    }
+}
+
+// A config file nests the keys of a subcommand under its name on the command line, the same as
+// dotted keys. `serde` expects the table of the variant under the name of the subcommand field.
+pub fn nest_subcommands_for_struct(fields: &[ParsedField]) -> TokenStream {
+    fields
+        .iter()
+        .filter(|f| !parse::is_configopt_field(f) && f.in_config_files())
+        .map(|field| {
+            let span = field.span();
+            let serde_name = field.serde_name();
+            let configopt_ty = field.configopt_ty();
+            if field.is_subcommand() {
+                quote_spanned! {span=>
+                    <#configopt_ty>::nest_subcommand_tables(table, #serde_name);
+                }
+            } else if field.is_structopt_flatten() && field.is_serde_flatten() {
+                quote_spanned! {span=>
+                    <#configopt_ty>::nest_subcommand_tables(table);
+                }
+            } else if field.is_structopt_flatten() {
+                quote_spanned! {span=>
+                    if let Some(::toml::Value::Table(table)) = table.get_mut(#serde_name) {
+                        <#configopt_ty>::nest_subcommand_tables(table);
+                    }
+                }
+            } else {
+                quote! {}
+            }
+        })
+        .collect()
+}
+
+pub fn nest_subcommands_for_enum(variants: &[ParsedVariant]) -> TokenStream {
+    variants
+        .iter()
+        .map(|variant| {
+            let structopt_name = variant.structopt_name();
+            let serde_name = variant.serde_name();
+            let nest = match variant.field_type() {
+                FieldType::Unnamed => {
+                    let configopt_ty = variant
+                        .unnamed_configopt_ty()
+                        .expect("unnamed variant to have a type");
+                    quote! {
+                        <#configopt_ty>::nest_subcommand_tables(table);
+                    }
+                }
+                FieldType::Named(fields) => nest_subcommands_for_struct(fields),
+                FieldType::Unit => return quote! {},
+            };
+            quote! {
+                if let Some(mut value) = table.remove(#structopt_name) {
+                    if let ::toml::Value::Table(table) = &mut value {
+                        #nest
+                    }
+                    let subcommand = table
+                        .entry(field)
+                        .or_insert_with(|| ::toml::Value::Table(::toml::value::Table::new()));
+                    if let ::toml::Value::Table(subcommand) = subcommand {
+                        subcommand.insert(String::from(#serde_name), value);
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+// Move the values of the old names of fields to their current names before anything else reads
+// the value tree. The value of the current name takes precedence if both are set. Deprecated keys
+// are reported by their path as written in the file, `written_prefix`.
+pub fn rename_aliases_for_struct(fields: &[ParsedField]) -> TokenStream {
+    fields
+        .iter()
+        .filter(|f| !parse::is_configopt_field(f) && !f.is_subcommand() && f.in_config_files())
+        .map(|field| {
+            let span = field.span();
+            let serde_name = field.serde_name();
+            let configopt_ty = field.configopt_ty();
+            let note = match field.deprecated() {
+                Some(note) => quote! {Some(String::from(#note))},
+                None => quote! {None},
+            };
+            // The name of a nested table as written is the prefix of the keys inside of it
+            let nested = field.is_structopt_flatten() && !field.is_serde_flatten();
+            let aliases = field.aliases().iter().map(|alias| {
+                let written_name = if nested {
+                    quote_spanned! {span=>
+                        if !table.contains_key(#serde_name) {
+                            written_name = #alias;
+                        }
+                    }
+                } else {
+                    quote! {}
+                };
+                quote_spanned! {span=>
+                    if let Some(value) = table.remove(#alias) {
+                        deprecated.push(::configopt::DeprecatedKey {
+                            path: [written_prefix, &[String::from(#alias)]].concat(),
+                            renamed_to: Some([prefix, &[String::from(#serde_name)]].concat()),
+                            note: #note,
+                        });
+                        #written_name
+                        table.entry(#serde_name).or_insert(value);
+                    }
+                }
+            });
+            let written_name = match (nested, field.aliases().is_empty()) {
+                (false, _) => quote! {},
+                (true, true) => quote! {let written_name = #serde_name;},
+                (true, false) => quote! {let mut written_name = #serde_name;},
+            };
+            let deprecated = if field.aliases().is_empty() && field.deprecated().is_some() {
+                quote_spanned! {span=>
+                    if table.contains_key(#serde_name) {
+                        deprecated.push(::configopt::DeprecatedKey {
+                            path: [written_prefix, &[String::from(#serde_name)]].concat(),
+                            renamed_to: None,
+                            note: #note,
+                        });
+                    }
+                }
+            } else {
+                quote! {}
+            };
+            let flatten = if field.is_structopt_flatten() && field.is_serde_flatten() {
+                quote_spanned! {span=>
+                    <#configopt_ty as ::configopt::ConfigOptType>::rename_aliases_with_prefix(
+                        table,
+                        prefix,
+                        written_prefix,
+                        deprecated,
+                    );
+                }
+            } else if field.is_structopt_flatten() {
+                quote_spanned! {span=>
+                    if let Some(::toml::Value::Table(table)) = table.get_mut(#serde_name) {
+                        <#configopt_ty as ::configopt::ConfigOptType>::rename_aliases_with_prefix(
+                            table,
+                            &[prefix, &[String::from(#serde_name)]].concat(),
+                            &[written_prefix, &[String::from(written_name)]].concat(),
+                            deprecated,
+                        );
+                    }
+                }
+            } else {
+                quote! {}
+            };
+            quote! {
+                {
+                    #written_name
+                    #(#aliases)*
+                    #deprecated
+                    #flatten
+                }
+            }
+        })
+        .collect()
+}
+
+pub fn config_version(config_version: Option<&ConfigVersion>) -> TokenStream {
+    match config_version {
+        Some(ConfigVersion { version, .. }) => quote! {Some(#version)},
+        None => quote! {None},
+    }
+}
+
+// Upgrade the raw value tree of an older config file before anything else reads it
+pub fn upgrade_for_struct(config_version: Option<&ConfigVersion>) -> TokenStream {
+    match config_version {
+        Some(ConfigVersion { version, upgrades }) => {
+            let upgrades = upgrades.iter().map(|upgrade| {
+                let from = upgrade.from;
+                let function = &upgrade.function;
+                quote! {(#from, #function as ::configopt::ConfigUpgrade)}
+            });
+            quote! {
+                ::configopt::upgrade_table(table, #version, &[#(#upgrades),*])
+            }
+        }
+        None => quote! {
+            let _ = table;
+            Ok(None)
+        },
+    }
+}
@@ -98,7 +98,7 @@ fn to_os_string(field: &ParsedField) -> TokenStream {
 pub fn for_struct(fields: &[ParsedField]) -> TokenStream {
     let normal_fields = fields
         .iter()
-        .filter(|f| !f.is_structopt_flatten() && !f.is_subcommand());
+        .filter(|f| !f.is_structopt_flatten() && !f.is_subcommand() && f.on_command_line());
     let normal_fields = normal_fields
         .map(|field| {
             let arg_name = field.structopt_name();
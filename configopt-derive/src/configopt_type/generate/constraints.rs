@@ -0,0 +1,132 @@
+use crate::configopt_type::parse::{self, Constraint, FieldType, ParsedField, ParsedVariant};
+use proc_macro2::TokenStream;
+use proc_macro_roids::IdentExt;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+
+fn constraint(constraint: &Constraint) -> TokenStream {
+    let pairs = |pairs: &[(String, String)]| {
+        let (first, second): (Vec<_>, Vec<_>) = pairs.iter().cloned().unzip();
+        quote! {vec![#((#first, #second)),*]}
+    };
+    match constraint {
+        Constraint::Required => quote! {::configopt::constraints::Constraint::Required},
+        Constraint::Requires(names) => {
+            quote! {::configopt::constraints::Constraint::Requires(vec![#(#names),*])}
+        }
+        Constraint::ConflictsWith(names) => {
+            quote! {::configopt::constraints::Constraint::ConflictsWith(vec![#(#names),*])}
+        }
+        Constraint::RequiredUnlessAny(names) => {
+            quote! {::configopt::constraints::Constraint::RequiredUnlessAny(vec![#(#names),*])}
+        }
+        Constraint::RequiredUnlessAll(names) => {
+            quote! {::configopt::constraints::Constraint::RequiredUnlessAll(vec![#(#names),*])}
+        }
+        Constraint::RequiredIfAny(p) => {
+            let p = pairs(p);
+            quote! {::configopt::constraints::Constraint::RequiredIfAny(#p)}
+        }
+        Constraint::RequiresIf(p) => {
+            let p = pairs(p);
+            quote! {::configopt::constraints::Constraint::RequiresIf(#p)}
+        }
+    }
+}
+
+// The arguments of a flattened struct share the namespace of the struct it is flattened into, a
+// subcommand starts a new namespace. Keys are named the same as in `get_with_path`.
+fn for_fields(fields: &[ParsedField], bound: bool) -> TokenStream {
+    fields
+        .iter()
+        .filter(|f| !parse::is_configopt_field(f) && f.on_command_line())
+        .map(|field| {
+            let span = field.span();
+            let serde_name = field.serde_name();
+            let field_ident = field.ident();
+            let access = if bound {
+                let bound_ident = field_ident.prepend("field_");
+                quote! {#bound_ident}
+            } else {
+                quote! {&self.#field_ident}
+            };
+            if field.is_subcommand() {
+                quote_spanned! {span=>
+                    if let Some(s) = (#access).as_ref() {
+                        let mut subcommand_group = Vec::new();
+                        ::configopt::ConfigOptType::constraint_args_with_prefix(s, prefix, &mut subcommand_group, groups);
+                        groups.push(subcommand_group);
+                    }
+                }
+            } else if field.is_structopt_flatten() && field.is_serde_flatten() {
+                quote_spanned! {span=>
+                    ::configopt::ConfigOptType::constraint_args_with_prefix(#access, prefix, group, groups);
+                }
+            } else if field.is_structopt_flatten() {
+                quote_spanned! {span=>
+                    let mut flatten_prefix = prefix.to_vec();
+                    flatten_prefix.push(String::from(#serde_name));
+                    ::configopt::ConfigOptType::constraint_args_with_prefix(#access, &flatten_prefix, group, groups);
+                }
+            } else {
+                let name = field.structopt_name();
+                let usage = field.usage();
+                let constraints = field.constraints().iter().map(constraint);
+                quote_spanned! {span=>
+                    group.push(::configopt::constraints::ConstraintArg {
+                        name: #name,
+                        usage: #usage,
+                        key: ::configopt::dotted_path(prefix, #serde_name),
+                        constraints: vec![#(#constraints),*],
+                    });
+                }
+            }
+        })
+        .collect()
+}
+
+pub fn for_struct(fields: &[ParsedField]) -> TokenStream {
+    for_fields(fields, false)
+}
+
+// The keys of a subcommand are nested under its name
+pub fn for_enum(variants: &[ParsedVariant]) -> TokenStream {
+    let arms = variants.iter().map(|variant| {
+        let structopt_name = variant.structopt_name();
+        let full_configopt_ident = variant.full_configopt_ident();
+        let (pattern, args) = match variant.field_type() {
+            FieldType::Unnamed => (
+                quote! {#full_configopt_ident(inner)},
+                quote! {
+                    ::configopt::ConfigOptType::constraint_args_with_prefix(inner, prefix, group, groups);
+                },
+            ),
+            FieldType::Named(fields) => {
+                let idents = fields.iter().map(|f| f.ident()).collect::<Vec<_>>();
+                let bound_idents = idents
+                    .iter()
+                    .map(|ident| ident.prepend("field_"))
+                    .collect::<Vec<_>>();
+                (
+                    quote! {#full_configopt_ident{#(#idents: #bound_idents),*}},
+                    for_fields(fields, true),
+                )
+            }
+            FieldType::Unit => (quote! {#full_configopt_ident}, quote! {}),
+        };
+        quote! {
+            #pattern => {
+                let mut prefix = prefix.to_vec();
+                prefix.push(String::from(#structopt_name));
+                let prefix = prefix.as_slice();
+                #args
+            }
+        }
+    });
+    quote! {
+        match self {
+            #(#arms)*
+            _ => {}
+        }
+    }
+}
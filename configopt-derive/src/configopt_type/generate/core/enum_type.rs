@@ -182,6 +182,90 @@ pub(crate) fn take_for(variants: &[ParsedVariant]) -> TokenStream {
         .collect()
 }
 
+// Generate the diff of `variants`. If `resolved` is set the variants are those of the full type
+// instead of the `ConfigOpt` type.
+pub(crate) fn diff(variants: &[ParsedVariant], resolved: bool) -> TokenStream {
+    variants
+        .iter()
+        .map(|variant| {
+            let ident = if resolved {
+                variant.full_ident()
+            } else {
+                variant.full_configopt_ident()
+            };
+            let structopt_name = variant.structopt_name();
+            match variant.field_type() {
+                FieldType::Unnamed => {
+                    let diff = if resolved {
+                        quote! {
+                            ::configopt::ConfigOpt::diff_with_prefix(self_variant, other_variant, prefix, changes);
+                        }
+                    } else {
+                        quote! {
+                            self_variant.diff_with_prefix(other_variant, prefix, changes);
+                        }
+                    };
+                    quote! {
+                        (#ident(self_variant), #ident(other_variant)) => {
+                            let prefix: &[String] = &[prefix, &[String::from(#structopt_name)][..]].concat();
+                            #diff
+                        }
+                    }
+                }
+                FieldType::Unit => {
+                    quote! {
+                        (#ident, #ident) => {}
+                    }
+                }
+                FieldType::Named(fields) => {
+                    let fields = fields.as_slice();
+                    let self_fields_match = comma_separated_fields("self_", fields, false);
+                    let other_fields_match = comma_separated_fields("other_", fields, false);
+                    let inner =
+                        struct_type::diff_with_prefix("self_", "other_", true, resolved, fields);
+                    quote! {
+                        (#ident{#self_fields_match}, #ident{#other_fields_match}) => {
+                            let prefix: &[String] = &[prefix, &[String::from(#structopt_name)][..]].concat();
+                            #inner
+                        }
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn variant_name(variants: &[ParsedVariant], resolved: bool) -> TokenStream {
+    // Handle the case of an empty enum
+    if variants.is_empty() {
+        return quote! {
+            _ => None
+        };
+    }
+    variants
+        .iter()
+        .map(|variant| {
+            let ident = if resolved {
+                variant.full_ident()
+            } else {
+                variant.full_configopt_ident()
+            };
+            let structopt_name = variant.structopt_name();
+            match variant.field_type() {
+                FieldType::Unnamed => quote! {
+                    #ident(_) => Some(#structopt_name),
+                },
+                FieldType::Unit => quote! {
+                    #ident => Some(#structopt_name),
+                },
+                FieldType::Named(_) => quote! {
+                    #ident{..} => Some(#structopt_name),
+                },
+            }
+        })
+        .collect()
+}
+
 pub(crate) fn is_empty(variants: &[ParsedVariant]) -> TokenStream {
     // Handle the case of an empty enum
     if variants.is_empty() {
@@ -1,4 +1,4 @@
-use crate::configopt_type::parse::{ParsedField, StructOptTy};
+use crate::configopt_type::parse::{self, ParsedField, StructOptTy};
 use proc_macro2::TokenStream;
 use quote::{quote, quote_spanned};
 use syn::{spanned::Spanned, Ident};
@@ -53,7 +53,9 @@ pub(crate) fn patch_with_prefix(
                 deref_other_field: _,
                 deref_self_field,
             } = FieldNames::new(field_ident, self_prefix, other_prefix, references);
-            if field.is_structopt_flatten() {
+            if field.is_skip() {
+                quote_spanned! {span=>}
+            } else if field.is_structopt_flatten() {
                 quote_spanned! {span=>
                     #self_field.patch(#other_field);
                 }
@@ -87,7 +89,9 @@ pub(crate) fn take_with_prefix(
                 deref_other_field: _,
                 deref_self_field,
             } = FieldNames::new(field_ident, self_prefix, other_prefix, references);
-            if field.is_structopt_flatten() {
+            if field.is_skip() {
+                quote_spanned! {span=>}
+            } else if field.is_structopt_flatten() {
                 quote_spanned! {span=>
                     #self_field.take(#other_field);
                 }
@@ -123,7 +127,9 @@ pub(crate) fn patch_for_with_prefix(
                 deref_self_field: _,
                 deref_other_field,
             } = FieldNames::new(field_ident, self_prefix, other_prefix, references);
-            if field.is_structopt_flatten() {
+            if field.is_skip() {
+                quote_spanned! {span=>}
+            } else if field.is_structopt_flatten() {
                 if field.no_wrap() {
                     quote_spanned! {span=>
                         #other_field.patch(#self_field);
@@ -172,7 +178,9 @@ pub(crate) fn take_for_with_prefix(
                 deref_other_field,
                 deref_self_field: _,
             } = FieldNames::new(field_ident, self_prefix, other_prefix, references);
-            if field.is_structopt_flatten() {
+            if field.is_skip() {
+                quote_spanned! {span=>}
+            } else if field.is_structopt_flatten() {
                 if field.no_wrap() {
                     quote_spanned! {span=>
                         #other_field.take(#self_field);
@@ -220,7 +228,11 @@ pub(crate) fn is_empty_with_prefix(prefix: &str, fields: &[ParsedField]) -> Toke
         let self_field = format!("{}{}", prefix, field_ident)
             .parse::<TokenStream>()
             .unwrap();
-        if field.is_structopt_flatten() {
+        if field.is_skip() {
+            quote_spanned! {span=>
+                true
+            }
+        } else if field.is_structopt_flatten() {
             quote_spanned! {span=>
                 #self_field.is_empty()
             }
@@ -250,7 +262,11 @@ pub(crate) fn is_complete_with_prefix(prefix: &str, fields: &[ParsedField]) -> T
         let self_field = format!("{}{}", prefix, field_ident)
             .parse::<TokenStream>()
             .unwrap();
-        if field.is_structopt_flatten() {
+        if field.is_skip() {
+            quote_spanned! {span=>
+                true
+            }
+        } else if field.is_structopt_flatten() {
             quote_spanned! {span=>
                 #self_field.is_complete()
             }
@@ -280,7 +296,11 @@ pub(crate) fn is_convertible_with_prefix(prefix: &str, fields: &[ParsedField]) -
         let self_field = format!("{}{}", prefix, field_ident)
             .parse::<TokenStream>()
             .unwrap();
-        if field.is_structopt_flatten() {
+        if field.is_skip() {
+            quote_spanned! {span=>
+                true
+            }
+        } else if field.is_structopt_flatten() {
             quote_spanned! {span=>
                 #self_field.is_convertible()
             }
@@ -313,12 +333,92 @@ pub(crate) fn is_convertible_with_prefix(prefix: &str, fields: &[ParsedField]) -
     }
 }
 
+// Generate the diff of `fields`. If `resolved` is set the fields are those of the full type instead
+// of the `ConfigOpt` type.
+pub(crate) fn diff_with_prefix(
+    self_prefix: &str,
+    other_prefix: &str,
+    references: bool,
+    resolved: bool,
+    fields: &[ParsedField],
+) -> TokenStream {
+    fields
+        .iter()
+        .filter(|f| !parse::is_configopt_field(f) && !f.is_skip())
+        .map(|field| {
+            let field_ident = field.ident();
+            let span = field.span();
+            let serde_name = field.serde_name();
+            let self_field = format!("{}{}", self_prefix, field_ident)
+                .parse::<TokenStream>()
+                .unwrap();
+            let other_field = format!("{}{}", other_prefix, field_ident)
+                .parse::<TokenStream>()
+                .unwrap();
+            let (self_field, other_field) = if references {
+                (self_field, other_field)
+            } else {
+                (quote! {&#self_field}, quote! {&#other_field})
+            };
+            if field.is_structopt_flatten() {
+                let prefix = if field.is_serde_flatten() {
+                    quote! {prefix}
+                } else {
+                    quote! {&[prefix, &[String::from(#serde_name)][..]].concat()}
+                };
+                if resolved && !field.no_wrap() {
+                    quote_spanned! {span=>
+                        ::configopt::ConfigOpt::diff_with_prefix(#self_field, #other_field, #prefix, changes);
+                    }
+                } else {
+                    quote_spanned! {span=>
+                        (#self_field).diff_with_prefix(#other_field, #prefix, changes);
+                    }
+                }
+            } else if field.is_subcommand() {
+                if resolved {
+                    quote_spanned! {span=>
+                        let self_name = ::configopt::ConfigOpt::variant_name(#self_field);
+                        let other_name = ::configopt::ConfigOpt::variant_name(#other_field);
+                        if self_name == other_name {
+                            ::configopt::ConfigOpt::diff_with_prefix(#self_field, #other_field, prefix, changes);
+                        } else {
+                            ::configopt::diff_value(prefix, #serde_name, &self_name, &other_name, changes);
+                        }
+                    }
+                } else {
+                    quote_spanned! {span=>
+                        let self_name = (#self_field).as_ref().and_then(|s| s.variant_name());
+                        let other_name = (#other_field).as_ref().and_then(|o| o.variant_name());
+                        match ((#self_field).as_ref(), (#other_field).as_ref()) {
+                            (Some(s), Some(o)) if self_name == other_name => {
+                                s.diff_with_prefix(o, prefix, changes);
+                            }
+                            _ => {
+                                ::configopt::diff_value(prefix, #serde_name, &self_name, &other_name, changes);
+                            }
+                        }
+                    }
+                }
+            } else {
+                quote_spanned! {span=>
+                    ::configopt::diff_value(prefix, #serde_name, #self_field, #other_field, changes);
+                }
+            }
+        })
+        .collect()
+}
+
 pub(crate) fn from(fields: &[ParsedField], other: &Ident) -> TokenStream {
     let field_tokens = fields.iter().map(|field| {
         let field_ident = field.ident();
         let span = field.span();
         let other_field = quote! {#other.#field_ident};
-        if field.is_structopt_flatten() {
+        if field.is_skip() {
+            quote_spanned! {span=>
+                #field_ident: Default::default(),
+            }
+        } else if field.is_structopt_flatten() {
             quote_spanned! {span=>
                 #field_ident: #other_field.into(),
             }
@@ -358,7 +458,11 @@ pub(crate) fn try_from(fields: &[ParsedField]) -> TokenStream {
         let span = field.span();
         let self_field = quote! {configopt.#field_ident};
         // We check upfront if the type `is_convertible` so all these `unwrap`'s are ok
-        if field.is_structopt_flatten() {
+        if field.is_skip() {
+            quote_spanned! {span=>
+                #field_ident: Default::default(),
+            }
+        } else if field.is_structopt_flatten() {
             quote_spanned! {span=>
                 #field_ident: #self_field.try_into().ok().unwrap(),
             }
@@ -408,6 +512,14 @@ pub(crate) fn take_for(fields: &[ParsedField]) -> TokenStream {
     take_for_with_prefix("self.", "other.", false, fields)
 }
 
+pub(crate) fn diff(fields: &[ParsedField]) -> TokenStream {
+    diff_with_prefix("self.", "other.", false, false, fields)
+}
+
+pub(crate) fn diff_resolved(fields: &[ParsedField]) -> TokenStream {
+    diff_with_prefix("self.", "other.", false, true, fields)
+}
+
 pub(crate) fn is_empty(fields: &[ParsedField]) -> TokenStream {
     is_empty_with_prefix("self.", fields)
 }
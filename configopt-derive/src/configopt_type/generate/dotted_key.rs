@@ -0,0 +1,284 @@
+use crate::configopt_type::parse::{self, FieldType, ParsedField, ParsedVariant};
+use proc_macro2::TokenStream;
+use proc_macro_roids::IdentExt;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+
+// Access a field of `self`, or a field bound by a variant pattern which is already a reference
+fn field_access(field: &ParsedField, bound: bool, mutable: bool) -> TokenStream {
+    let field_ident = field.ident();
+    if bound {
+        let bound_ident = field_ident.prepend("field_");
+        quote! {#bound_ident}
+    } else if mutable {
+        quote! {&mut self.#field_ident}
+    } else {
+        quote! {&self.#field_ident}
+    }
+}
+
+// Keys are named the same as in `toml_config_with_prefix`. The fields of a flattened struct are
+// nested under its name unless it is also flattened by serde, the keys of a subcommand are nested
+// under its name on the command line.
+fn get_for_fields(fields: &[ParsedField], bound: bool) -> TokenStream {
+    fields
+        .iter()
+        // A `cli_only` field is read by validators and constraints but can not be set from a file
+        .filter(|f| !parse::is_configopt_field(f) && !f.is_skip())
+        .map(|field| {
+            let span = field.span();
+            let serde_name = field.serde_name();
+            let configopt_ty = field.configopt_ty();
+            let access = field_access(field, bound, false);
+            if field.is_subcommand() {
+                quote_spanned! {span=>
+                    if let Some(value) = <#configopt_ty>::get_subcommand_with_path((#access).as_ref(), path) {
+                        return Some(value);
+                    }
+                }
+            } else if field.is_structopt_flatten() && field.is_serde_flatten() {
+                quote_spanned! {span=>
+                    if let Some(value) = ::configopt::ConfigOptType::get_with_path(#access, path) {
+                        return Some(value);
+                    }
+                }
+            } else if field.is_structopt_flatten() {
+                quote_spanned! {span=>
+                    match path {
+                        [#serde_name] => return Some(::configopt::to_toml_value(#access)),
+                        [#serde_name, path @ ..] => {
+                            return ::configopt::ConfigOptType::get_with_path(#access, path);
+                        }
+                        _ => {}
+                    }
+                }
+            } else {
+                quote_spanned! {span=>
+                    if let [#serde_name] = path {
+                        return Some(::configopt::to_toml_value(#access));
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+fn set_for_fields(fields: &[ParsedField], bound: bool) -> TokenStream {
+    fields
+        .iter()
+        .filter(|f| !parse::is_configopt_field(f) && f.in_config_files())
+        .map(|field| {
+            let span = field.span();
+            let serde_name = field.serde_name();
+            let configopt_ty = field.configopt_ty();
+            let access = field_access(field, bound, true);
+            if field.is_subcommand() {
+                quote_spanned! {span=>
+                    if let Some(result) = <#configopt_ty>::set_subcommand_with_path(#access, path, value.clone()) {
+                        return Some(result);
+                    }
+                }
+            } else if field.is_structopt_flatten() && field.is_serde_flatten() {
+                quote_spanned! {span=>
+                    if let Some(result) = ::configopt::ConfigOptType::set_with_path(#access, path, value.clone()) {
+                        return Some(result);
+                    }
+                }
+            } else if field.is_structopt_flatten() {
+                quote_spanned! {span=>
+                    match path {
+                        [#serde_name] => return Some(::configopt::from_toml_value_into(#access, value)),
+                        [#serde_name, path @ ..] => {
+                            return ::configopt::ConfigOptType::set_with_path(#access, path, value);
+                        }
+                        _ => {}
+                    }
+                }
+            } else {
+                quote_spanned! {span=>
+                    if let [#serde_name] = path {
+                        return Some(::configopt::from_toml_value_into(#access, value));
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+// Old names of fields are accepted the same as in config files
+fn rename_aliases(fields: &[ParsedField]) -> TokenStream {
+    let (aliases, names): (Vec<_>, Vec<_>) = fields
+        .iter()
+        .flat_map(|f| f.aliases().iter().map(move |alias| (alias, f.serde_name())))
+        .unzip();
+    if aliases.is_empty() {
+        return quote! {};
+    }
+    quote! {
+        let path = ::configopt::rename_alias(path, &[#((#aliases, #names)),*]);
+        let path = path.as_slice();
+    }
+}
+
+pub fn get_for_struct(fields: &[ParsedField]) -> TokenStream {
+    let rename_aliases = rename_aliases(fields);
+    let get = get_for_fields(fields, false);
+    quote! {
+        #rename_aliases
+        #get
+        None
+    }
+}
+
+pub fn set_for_struct(fields: &[ParsedField]) -> TokenStream {
+    let rename_aliases = rename_aliases(fields);
+    let set = set_for_fields(fields, false);
+    quote! {
+        #rename_aliases
+        #set
+        None
+    }
+}
+
+// A variant with every field unset and the pattern binding its fields
+fn variant_parts(variant: &ParsedVariant) -> (TokenStream, TokenStream) {
+    let full_configopt_ident = variant.full_configopt_ident();
+    match variant.field_type() {
+        FieldType::Unnamed => (
+            quote! {#full_configopt_ident(::std::default::Default::default())},
+            quote! {#full_configopt_ident(inner)},
+        ),
+        FieldType::Named(fields) => {
+            let idents = fields.iter().map(|f| f.ident()).collect::<Vec<_>>();
+            let bound_idents = idents
+                .iter()
+                .map(|ident| ident.prepend("field_"))
+                .collect::<Vec<_>>();
+            (
+                quote! {#full_configopt_ident{#(#idents: ::std::default::Default::default()),*}},
+                quote! {#full_configopt_ident{#(#idents: #bound_idents),*}},
+            )
+        }
+        FieldType::Unit => (
+            quote! {#full_configopt_ident},
+            quote! {#full_configopt_ident},
+        ),
+    }
+}
+
+// Keys of a subcommand that is not set are still known, they are checked against a variant with
+// every field unset
+pub fn get_for_enum(variants: &[ParsedVariant]) -> TokenStream {
+    let arms = variants.iter().map(|variant| {
+        let structopt_name = variant.structopt_name();
+        let full_configopt_ident = variant.full_configopt_ident();
+        let (unset, pattern) = variant_parts(variant);
+        let inner = match variant.field_type() {
+            FieldType::Unnamed => quote! {
+                ::configopt::ConfigOptType::get_with_path(inner, path)
+            },
+            FieldType::Named(fields) => {
+                let get = get_for_fields(fields, true);
+                quote! {
+                    (|| {
+                        #get
+                        None
+                    })()
+                }
+            }
+            FieldType::Unit => quote! {None},
+        };
+        quote! {
+            [#structopt_name, path @ ..] => {
+                let unset = #unset;
+                let is_set = matches!(this, Some(#full_configopt_ident{..}));
+                let variant = match this {
+                    Some(variant) if is_set => variant,
+                    _ => &unset,
+                };
+                let result = match variant {
+                    #pattern => #inner,
+                    _ => None,
+                };
+                if is_set {
+                    result
+                } else {
+                    result.map(|_| None)
+                }
+            }
+        }
+    });
+    quote! {
+        match path {
+            #(#arms)*
+            _ => None,
+        }
+    }
+}
+
+// Setting a key of another subcommand switches to that subcommand
+pub fn set_for_enum(variants: &[ParsedVariant]) -> TokenStream {
+    let arms = variants.iter().map(|variant| {
+        let structopt_name = variant.structopt_name();
+        let full_configopt_ident = variant.full_configopt_ident();
+        let (unset, pattern) = variant_parts(variant);
+        let inner = match variant.field_type() {
+            FieldType::Unnamed => quote! {
+                ::configopt::ConfigOptType::set_with_path(inner, path, value)
+            },
+            FieldType::Named(fields) => {
+                let set = set_for_fields(fields, true);
+                quote! {
+                    (|| {
+                        #set
+                        None
+                    })()
+                }
+            }
+            FieldType::Unit => quote! {None},
+        };
+        quote! {
+            [#structopt_name, path @ ..] => {
+                let mut unset = #unset;
+                let is_set = matches!(this, Some(#full_configopt_ident{..}));
+                let variant = match this {
+                    Some(variant) if is_set => variant,
+                    _ => &mut unset,
+                };
+                let result = match variant {
+                    #pattern => #inner,
+                    _ => None,
+                };
+                if !is_set {
+                    if let Some(Ok(())) = result {
+                        *this = Some(unset);
+                    }
+                }
+                result
+            }
+        }
+    });
+    quote! {
+        match path {
+            #(#arms)*
+            _ => None,
+        }
+    }
+}
+
+// The variant can not be changed through `&mut self`, so `self` is temporarily replaced with
+// another variant while it is set as an `Option`
+pub fn set_for_enum_self(variants: &[ParsedVariant]) -> TokenStream {
+    match variants.first() {
+        Some(variant) => {
+            let (placeholder, _) = variant_parts(variant);
+            quote! {
+                let mut this = Some(::std::mem::replace(self, #placeholder));
+                let result = Self::set_subcommand_with_path(&mut this, path, value);
+                *self = this.expect("subcommand to remain set");
+                result
+            }
+        }
+        None => quote! {None},
+    }
+}
@@ -36,15 +36,31 @@ pub fn patch_for_struct(parsed: &[ParsedField], configopt_ident: &Ident) -> Toke
         .collect::<TokenStream>();
     if has_config_fields {
         quote! {
-            use ::std::convert::TryFrom;
-            let mut from_default_config_files = #configopt_ident::from_default_config_files()?;
-            let mut from_config_files = if let Some(config_files) = &self.config_files {
-                let mut from_config_files = #configopt_ident::try_from(config_files.as_slice())?;
-                from_config_files.patch(&mut from_default_config_files);
-                from_config_files
-            } else {
-                from_default_config_files
+            // Each file is read once, its values are both merged and recorded. Later files take
+            // precedence, so the config files given on the command line override the default ones.
+            let mut from_config_files = #configopt_ident::default();
+            let mut merge = |path: ::std::path::PathBuf, mut value: ::toml::Value| -> ::configopt::Result<()> {
+                if let ::toml::Value::Table(table) = &mut value {
+                    #configopt_ident::nest_subcommand_tables(table);
+                }
+                from_config_files.take(&mut ::configopt::from_toml_value(&path, value.clone())?);
+                let values = ::configopt::from_toml_value(&path, value)?;
+                files.push((path, values));
+                Ok(())
             };
+            for path in #configopt_ident::default_config_files() {
+                match #configopt_ident::read_config_value(&path) {
+                    Ok(value) => merge(path, value)?,
+                    // If we could not find a default config file do nothing
+                    Err(e) if e.config_file_not_found() => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            // Unlike a default config file, a config file given on the command line must exist
+            for path in self.config_files.clone().unwrap_or_default() {
+                let value = #configopt_ident::read_config_value(&path)?;
+                merge(path, value)?;
+            }
             self.patch(&mut from_config_files);
             #patch_subcommands
             Ok(self)
@@ -57,6 +73,113 @@ pub fn patch_for_struct(parsed: &[ParsedField], configopt_ident: &Ident) -> Toke
     }
 }
 
+pub fn paths_for_struct(parsed: &[ParsedField], configopt_ident: &Ident) -> TokenStream {
+    let has_config_fields = parse::has_configopt_fields(parsed);
+    let subcommand_paths = parsed
+        .iter()
+        .filter(|f| f.is_subcommand())
+        .map(|field| {
+            let field_ident = field.ident();
+            let self_field = quote! {self.#field_ident};
+            quote! {
+                if let Some(s) = #self_field.as_ref() {
+                    paths.extend(s.config_file_paths());
+                }
+            }
+        })
+        .collect::<TokenStream>();
+    if has_config_fields {
+        quote! {
+            let mut paths = #configopt_ident::default_config_files();
+            if let Some(config_files) = &self.config_files {
+                paths.extend(config_files.iter().cloned());
+            }
+            #subcommand_paths
+            paths
+        }
+    } else {
+        quote! {
+            let mut paths = Vec::new();
+            #subcommand_paths
+            paths
+        }
+    }
+}
+
+pub fn check_requested_for_struct(parsed: &[ParsedField]) -> TokenStream {
+    if parse::has_configopt_fields(parsed) {
+        quote! {
+            self.check_config.unwrap_or_default()
+        }
+    } else {
+        quote! {
+            false
+        }
+    }
+}
+
+pub fn explain_requested_for_struct(parsed: &[ParsedField]) -> TokenStream {
+    if parse::has_configopt_fields(parsed) {
+        quote! {
+            self.explain_config.unwrap_or_default()
+        }
+    } else {
+        quote! {
+            false
+        }
+    }
+}
+
+// The name of the `--config-files` argument, it is renamed like the other arguments
+pub fn config_files_arg_for_struct(parsed: &[ParsedField]) -> TokenStream {
+    match parsed.iter().find(|f| f.ident() == "config_files") {
+        Some(field) if parse::has_configopt_fields(parsed) => {
+            let name = field.structopt_rename().rename("config-files");
+            quote! {
+                Some(#name)
+            }
+        }
+        _ => quote! {
+            None
+        },
+    }
+}
+
+// Check each of this type's own config files, then the config files of the subcommand
+pub fn check_for_struct(parsed: &[ParsedField]) -> TokenStream {
+    let check_subcommands = parsed
+        .iter()
+        .filter(|f| f.is_subcommand())
+        .map(|field| {
+            let field_ident = field.ident();
+            quote! {
+                if let Some(s) = self.#field_ident.as_mut() {
+                    s.check_config_files(problems);
+                }
+            }
+        })
+        .collect::<TokenStream>();
+    if parse::has_configopt_fields(parsed) {
+        quote! {
+            for path in Self::default_config_files() {
+                let value = Self::read_config_value(&path);
+                ::configopt::check_config_file(self, &path, value, problems);
+            }
+            for path in self.config_files.clone().unwrap_or_default() {
+                match Self::read_config_value(&path) {
+                    // Unlike a default config file, a config file given on the command line must
+                    // exist
+                    Err(e) if e.config_file_not_found() => problems.push(e.to_string()),
+                    value => ::configopt::check_config_file(self, &path, value, problems),
+                }
+            }
+            #check_subcommands
+        }
+    } else {
+        check_subcommands
+    }
+}
+
 pub fn generate_for_enum(variants: &[ParsedVariant]) -> TokenStream {
     variants
         .iter()
@@ -78,6 +201,23 @@ pub fn generate_for_enum(variants: &[ParsedVariant]) -> TokenStream {
         .collect()
 }
 
+pub fn paths_for_enum(variants: &[ParsedVariant]) -> TokenStream {
+    variants
+        .iter()
+        .map(|variant| match variant.field_type() {
+            FieldType::Unnamed => {
+                let full_configopt_ident = variant.full_configopt_ident();
+                quote! {
+                    #full_configopt_ident(variant) => variant.config_file_paths(),
+                }
+            }
+            FieldType::Named(_) | FieldType::Unit => {
+                quote! {}
+            }
+        })
+        .collect()
+}
+
 pub fn patch_for_enum(variants: &[ParsedVariant]) -> TokenStream {
     variants
         .iter()
@@ -97,3 +237,20 @@ pub fn patch_for_enum(variants: &[ParsedVariant]) -> TokenStream {
         })
         .collect()
 }
+
+pub fn check_for_enum(variants: &[ParsedVariant]) -> TokenStream {
+    variants
+        .iter()
+        .map(|variant| match variant.field_type() {
+            FieldType::Unnamed => {
+                let full_configopt_ident = variant.full_configopt_ident();
+                quote! {
+                    #full_configopt_ident(variant) => variant.check_config_files(problems),
+                }
+            }
+            FieldType::Named(_) | FieldType::Unit => {
+                quote! {}
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,142 @@
+use crate::configopt_type::parse::{self, FieldType, ParsedField, ParsedVariant, StructOptTy};
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::{spanned::Spanned, Type};
+
+// Map the type of a single value to its JSON type
+pub(crate) fn json_type(ty: &Type) -> Option<&'static str> {
+    let ident = match parse::value_ty(ty) {
+        Type::Path(type_path) => type_path.path.segments.last()?.ident.to_string(),
+        _ => return None,
+    };
+    match ident.as_str() {
+        "String" | "PathBuf" | "OsString" | "char" => Some("string"),
+        "bool" => Some("boolean"),
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64" | "u128"
+        | "usize" => Some("integer"),
+        "f32" | "f64" => Some("number"),
+        _ => None,
+    }
+}
+
+// Lookup the argument of a field, a `file_only` field is only described by its doc comment
+pub(crate) fn arg_info(field: &ParsedField) -> TokenStream {
+    if field.is_file_only() {
+        let doc = field.doc();
+        quote! {
+            Some(::configopt::ArgInfo {
+                help: String::from(#doc),
+                ..::configopt::ArgInfo::default()
+            })
+        }
+    } else {
+        let structopt_name = field.structopt_name();
+        quote! {::configopt::arg_info(app, #structopt_name)}
+    }
+}
+
+pub fn for_struct(fields: &[ParsedField]) -> TokenStream {
+    let field_tokens = fields.iter().map(|field| {
+        let span = field.span();
+        let serde_name = field.serde_name();
+        let configopt_ty = field.configopt_ty();
+        if parse::is_configopt_field(field) || !field.in_config_files() {
+            quote! {}
+        } else if field.is_subcommand() {
+            quote_spanned! {span=>
+                <#configopt_ty>::json_schema_subcommands(properties, definitions);
+            }
+        } else if field.is_structopt_flatten() && field.is_serde_flatten() {
+            // The fields of a flattened struct live in the same table
+            quote_spanned! {span=>
+                <#configopt_ty>::json_schema_properties(properties, definitions);
+            }
+        } else if field.is_structopt_flatten() {
+            // Definitions are kept in the document so they can be referenced from the root
+            quote_spanned! {span=>
+                let mut nested_properties = ::configopt::json_schema::Map::new();
+                <#configopt_ty>::json_schema_properties(
+                    &mut nested_properties,
+                    definitions,
+                );
+                properties.insert(
+                    String::from(#serde_name),
+                    ::configopt::json_schema::object(
+                        nested_properties,
+                        ::configopt::json_schema::Map::new(),
+                    ),
+                );
+            }
+        } else {
+            let arg_info = arg_info(field);
+            let json_type = match json_type(field.ty()) {
+                Some(json_type) => quote! {Some(#json_type)},
+                None => quote! {None},
+            };
+            let array = matches!(
+                field.structopt_ty(),
+                StructOptTy::Vec | StructOptTy::OptionVec
+            );
+            quote_spanned! {span=>
+                let info = #arg_info.unwrap_or_default();
+                properties.insert(
+                    String::from(#serde_name),
+                    ::configopt::json_schema::property(#json_type, #array, &info),
+                );
+            }
+        }
+    });
+    quote! {
+        #(#field_tokens)*
+    }
+}
+
+// The table of each subcommand is a property nested under its name, the same as dotted keys,
+// referencing the schema of the subcommand in the definitions
+pub fn for_enum(variants: &[ParsedVariant]) -> TokenStream {
+    variants
+        .iter()
+        .map(|variant| {
+            let structopt_name = variant.structopt_name();
+            let inner = match variant.field_type() {
+                FieldType::Unnamed => {
+                    let configopt_ty = variant
+                        .unnamed_configopt_ty()
+                        .expect("unnamed variant to have a type");
+                    quote! {
+                        <#configopt_ty>::json_schema_properties(
+                            &mut variant_properties,
+                            definitions,
+                        );
+                    }
+                }
+                FieldType::Unit => quote! {},
+                FieldType::Named(fields) => {
+                    let inner = for_struct(fields);
+                    quote! {
+                        if let Some(app) = ::configopt::subcommand_app(app, #structopt_name) {
+                            let properties = &mut variant_properties;
+                            #inner
+                        }
+                    }
+                }
+            };
+            quote! {
+                #[allow(unused_mut)]
+                let mut variant_properties = ::configopt::json_schema::Map::new();
+                #inner
+                definitions.insert(
+                    String::from(#structopt_name),
+                    ::configopt::json_schema::object(
+                        variant_properties,
+                        ::configopt::json_schema::Map::new(),
+                    ),
+                );
+                properties.insert(
+                    String::from(#structopt_name),
+                    ::configopt::json_schema::reference(#structopt_name),
+                );
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,121 @@
+use super::json_schema::{arg_info, json_type};
+use crate::configopt_type::parse::{self, FieldType, ParsedField, ParsedVariant, StructOptTy};
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned, ToTokens};
+use syn::{spanned::Spanned, Type};
+
+// A human readable name for the type of a field
+fn type_name(field: &ParsedField) -> String {
+    let value_ty = parse::value_ty(field.ty());
+    let name = json_type(field.ty()).map(String::from).unwrap_or_else(|| {
+        match value_ty {
+            Type::Path(type_path) => type_path
+                .path
+                .segments
+                .last()
+                .map(|segment| segment.ident.to_string()),
+            _ => None,
+        }
+        .unwrap_or_else(|| value_ty.to_token_stream().to_string())
+    });
+    match field.structopt_ty() {
+        StructOptTy::Vec | StructOptTy::OptionVec => format!("array of {}", name),
+        _ => name,
+    }
+}
+
+pub fn for_struct(fields: &[ParsedField]) -> TokenStream {
+    let field_tokens = fields.iter().map(|field| {
+        let span = field.span();
+        let serde_name = field.serde_name();
+        let configopt_ty = field.configopt_ty();
+        if parse::is_configopt_field(field) || !field.in_config_files() {
+            quote! {}
+        } else if field.is_subcommand() {
+            quote_spanned! {span=>
+                <#configopt_ty>::reference_subcommands(
+                    app,
+                    &section.command,
+                    serde_prefix,
+                    &mut subcommands,
+                );
+            }
+        } else if field.is_structopt_flatten() {
+            let prefix = if field.is_serde_flatten() {
+                quote! {serde_prefix}
+            } else {
+                quote! {&[serde_prefix, &[String::from(#serde_name)][..]].concat()}
+            };
+            quote_spanned! {span=>
+                <#configopt_ty>::reference_sections(
+                    app,
+                    #prefix,
+                    ::configopt::reference::Section::flattened(&section, #serde_name),
+                    &mut flattened,
+                );
+            }
+        } else {
+            let arg_info = arg_info(field);
+            let type_name = type_name(field);
+            quote_spanned! {span=>
+                if let Some(info) = #arg_info {
+                    if !info.hidden {
+                        section.entries.push(::configopt::reference::Entry::new(
+                            ::configopt::dotted_path(serde_prefix, #serde_name),
+                            #type_name,
+                            info,
+                        ));
+                    }
+                }
+            }
+        }
+    });
+    // Flattened sections directly follow their parent so they are grouped with its command
+    quote! {
+        let mut flattened = Vec::new();
+        let mut subcommands = Vec::new();
+        #(#field_tokens)*
+        sections.push(section);
+        sections.append(&mut flattened);
+        sections.append(&mut subcommands);
+    }
+}
+
+pub fn for_enum(variants: &[ParsedVariant]) -> TokenStream {
+    variants
+        .iter()
+        .map(|variant| {
+            let structopt_name = variant.structopt_name();
+            let inner = match variant.field_type() {
+                FieldType::Unnamed => {
+                    let configopt_ty = variant
+                        .unnamed_configopt_ty()
+                        .expect("unnamed variant to have a type");
+                    quote! {
+                        <#configopt_ty>::reference_sections(app, serde_prefix, section, sections);
+                    }
+                }
+                FieldType::Unit => {
+                    quote! {
+                        sections.push(section);
+                    }
+                }
+                FieldType::Named(fields) => {
+                    let inner = for_struct(fields);
+                    quote! {
+                        let mut section = section;
+                        #inner
+                    }
+                }
+            };
+            // The keys of a subcommand are nested under its name, the same as dotted keys
+            quote! {
+                if let Some(app) = ::configopt::subcommand_app(app, #structopt_name) {
+                    let section = ::configopt::reference::Section::command(app, Some(command));
+                    let serde_prefix: &[String] = &[serde_prefix, &[String::from(#structopt_name)][..]].concat();
+                    #inner
+                }
+            }
+        })
+        .collect()
+}
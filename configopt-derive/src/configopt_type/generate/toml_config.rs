@@ -9,7 +9,7 @@ pub fn for_struct(fields: &[ParsedField]) -> TokenStream {
         let self_field = quote! {self.#field_ident};
         let span = field.span();
         let serde_name = field.serde_name();
-        if field.is_subcommand() {
+        if field.is_subcommand() || !field.in_config_files() {
             quote! {}
         } else if field.is_serde_flatten() {
             quote_spanned! {span=>
@@ -20,6 +20,15 @@ pub fn for_struct(fields: &[ParsedField]) -> TokenStream {
             let structopt_rename = field.structopt_rename();
             let generate_config_arg_name = structopt_rename.rename("generate-config"); 
             let config_files_arg_name = structopt_rename.rename("config-files"); 
+            let check_config_arg_name = structopt_rename.rename("check-config");
+            let explain_config_arg_name = structopt_rename.rename("explain-config");
+            // A `file_only` field has no argument to take the comment from
+            let doc_comment = if field.is_file_only() {
+                let doc = field.doc();
+                quote! {comment = String::from(#doc);}
+            } else {
+                quote! {}
+            };
             quote_spanned! {span=>
                 let key = if serde_prefix.is_empty() {
                     String::from(#serde_name)
@@ -66,7 +75,8 @@ pub fn for_struct(fields: &[ParsedField]) -> TokenStream {
                         }
                     }
                 }
-                if !hidden && !&[#generate_config_arg_name, #config_files_arg_name].contains(&#structopt_name) {
+                #doc_comment
+                if !hidden && !&[#generate_config_arg_name, #config_files_arg_name, #check_config_arg_name, #explain_config_arg_name].contains(&#structopt_name) {
                     if !comment.is_empty() {
                         comment = comment.lines().map(|l| format!("### {}\n", l)).collect::<String>();
                     }
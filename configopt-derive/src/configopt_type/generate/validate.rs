@@ -0,0 +1,156 @@
+use crate::configopt_type::parse::{
+    self, FieldType, ParsedField, ParsedVariant, StructOptTy, Validator,
+};
+use proc_macro2::TokenStream;
+use proc_macro_roids::IdentExt;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+
+// The value of a field as an `Option` of a reference. Fields bound by a variant pattern are
+// already references.
+fn value(field: &ParsedField, bound: bool) -> TokenStream {
+    let field_ident = field.ident();
+    let access = if bound {
+        let bound_ident = field_ident.prepend("field_");
+        quote! {#bound_ident}
+    } else {
+        quote! {self.#field_ident}
+    };
+    match field.structopt_ty() {
+        StructOptTy::OptionOption => quote! {(#access).as_ref().and_then(Option::as_ref)},
+        StructOptTy::Vec if field.is_positional_vec() && bound => quote! {Some(#access)},
+        StructOptTy::Vec if field.is_positional_vec() => quote! {Some(&#access)},
+        _ => quote! {(#access).as_ref()},
+    }
+}
+
+fn call(validator: &Validator) -> TokenStream {
+    match validator {
+        Validator::Range(range) => quote! {::configopt::validate::range(value, #range)},
+        Validator::Regex(regex) => quote! {::configopt::validate::regex(value, #regex)},
+        Validator::NonEmpty => quote! {::configopt::validate::non_empty(value)},
+        Validator::PathExists => quote! {::configopt::validate::path_exists(value)},
+        Validator::With(f) => quote! {(#f)(value)},
+    }
+}
+
+// Every validator but `non_empty` checks each element of a `Vec`
+fn check(field: &ParsedField, validator: &Validator) -> TokenStream {
+    let call = call(validator);
+    let is_vec = matches!(
+        field.structopt_ty(),
+        StructOptTy::Vec | StructOptTy::OptionVec
+    );
+    if is_vec && !matches!(validator, Validator::NonEmpty) {
+        quote! {
+            for value in value.iter() {
+                if let Err(e) = #call {
+                    failures.push((key.clone(), e));
+                }
+            }
+        }
+    } else {
+        quote! {
+            if let Err(e) = #call {
+                failures.push((key.clone(), e));
+            }
+        }
+    }
+}
+
+// Keys are named the same as in `get_with_path`
+fn for_fields(fields: &[ParsedField], bound: bool) -> TokenStream {
+    fields
+        .iter()
+        .filter(|f| !parse::is_configopt_field(f) && !f.is_skip())
+        .map(|field| {
+            let span = field.span();
+            let serde_name = field.serde_name();
+            let field_ident = field.ident();
+            let access = if bound {
+                let bound_ident = field_ident.prepend("field_");
+                quote! {#bound_ident}
+            } else {
+                quote! {&self.#field_ident}
+            };
+            if field.is_subcommand() {
+                quote_spanned! {span=>
+                    if let Some(s) = (#access).as_ref() {
+                        ::configopt::ConfigOptType::validate_with_prefix(s, prefix, failures);
+                    }
+                }
+            } else if field.is_structopt_flatten() && field.is_serde_flatten() {
+                quote_spanned! {span=>
+                    ::configopt::ConfigOptType::validate_with_prefix(#access, prefix, failures);
+                }
+            } else if field.is_structopt_flatten() {
+                quote_spanned! {span=>
+                    let mut flatten_prefix = prefix.to_vec();
+                    flatten_prefix.push(String::from(#serde_name));
+                    ::configopt::ConfigOptType::validate_with_prefix(#access, &flatten_prefix, failures);
+                }
+            } else if field.validators().is_empty() {
+                quote! {}
+            } else {
+                let value = value(field, bound);
+                let checks = field
+                    .validators()
+                    .iter()
+                    .map(|v| check(field, v))
+                    .collect::<TokenStream>();
+                quote_spanned! {span=>
+                    if let Some(value) = #value {
+                        let key = ::configopt::dotted_path(prefix, #serde_name);
+                        #checks
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+pub fn for_struct(fields: &[ParsedField]) -> TokenStream {
+    for_fields(fields, false)
+}
+
+// The keys of a subcommand are nested under its name
+pub fn for_enum(variants: &[ParsedVariant]) -> TokenStream {
+    let arms = variants.iter().map(|variant| {
+        let structopt_name = variant.structopt_name();
+        let full_configopt_ident = variant.full_configopt_ident();
+        let (pattern, validate) = match variant.field_type() {
+            FieldType::Unnamed => (
+                quote! {#full_configopt_ident(inner)},
+                quote! {
+                    ::configopt::ConfigOptType::validate_with_prefix(inner, prefix, failures);
+                },
+            ),
+            FieldType::Named(fields) => {
+                let idents = fields.iter().map(|f| f.ident()).collect::<Vec<_>>();
+                let bound_idents = idents
+                    .iter()
+                    .map(|ident| ident.prepend("field_"))
+                    .collect::<Vec<_>>();
+                (
+                    quote! {#full_configopt_ident{#(#idents: #bound_idents),*}},
+                    for_fields(fields, true),
+                )
+            }
+            FieldType::Unit => (quote! {#full_configopt_ident}, quote! {}),
+        };
+        quote! {
+            #pattern => {
+                let mut prefix = prefix.to_vec();
+                prefix.push(String::from(#structopt_name));
+                let prefix = prefix.as_slice();
+                #validate
+            }
+        }
+    });
+    quote! {
+        match self {
+            #(#arms)*
+            _ => {}
+        }
+    }
+}
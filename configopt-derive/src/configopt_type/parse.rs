@@ -1,22 +1,29 @@
 #[macro_use]
 mod attribute_trimmer;
+mod config_version_parser;
 pub mod configopt_fields_attr_parser;
 mod configopt_parser;
 mod serde_parser;
 mod structopt_parser;
 
 use configopt_parser::ConfigOptAttr;
+pub use configopt_parser::Validator;
 use heck::{CamelCase, KebabCase, MixedCase, ShoutySnakeCase, SnakeCase};
 use proc_macro2::{Span, TokenStream};
 use proc_macro_roids::IdentExt;
 use serde_parser::SerdeAttr;
 use std::{convert::Infallible, str::FromStr};
 use structopt_parser::StructOptAttr;
-use syn::{parse_quote, spanned::Spanned, Attribute, Expr, Field, Fields, Ident, Type, Variant};
+use syn::{
+    parse_quote, spanned::Spanned, Attribute, Expr, Field, Fields, Ident, Lit, Meta, MetaNameValue,
+    Type, Variant,
+};
 
+pub use config_version_parser::{parse_and_trim as config_version, ConfigVersion};
 pub use serde_parser::trim_attr as trim_serde_attr;
 pub use structopt_parser::{
-    rename_all as structopt_rename_all, trim_attr as trim_structopt_attr, StructOptTy,
+    rename_all as structopt_rename_all, trim_attr as trim_structopt_attr, value_ty, Constraint,
+    StructOptTy,
 };
 
 pub fn configopt_ident(ident: &Ident) -> Ident {
@@ -84,21 +91,94 @@ pub fn has_configopt_fields(parsed: &[ParsedField]) -> bool {
     parsed.iter().any(|f| f.ident() == "generate_config")
 }
 
+// Is this one of the fields added by `#[configopt_fields]`
+pub fn is_configopt_field(field: &ParsedField) -> bool {
+    field.ident() == "generate_config"
+        || field.ident() == "config_files"
+        || field.ident() == "check_config"
+        || field.ident() == "explain_config"
+}
+
+/// Where the value of a field comes from, set with `#[configopt(skip)]`,
+/// `#[configopt(cli_only)]` or `#[configopt(file_only)]`. A `skip` or `file_only` field must also
+/// have `#[structopt(skip)]` as it is not an argument of the original struct either.
+#[derive(Clone, Copy, PartialEq)]
+pub enum FieldSource {
+    CliAndFile,
+    CliOnly,
+    FileOnly,
+    Skip,
+}
+
+impl FieldSource {
+    fn on_command_line(self) -> bool {
+        matches!(self, Self::CliAndFile | Self::CliOnly)
+    }
+
+    fn in_config_files(self) -> bool {
+        matches!(self, Self::CliAndFile | Self::FileOnly)
+    }
+}
+
+// Only a single path or a list of paths can be resolved against the config file directory
+fn is_path_buf(structopt_ty: StructOptTy, ty: &Type) -> bool {
+    let is_path_buf = match value_ty(ty) {
+        Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "PathBuf"),
+        _ => false,
+    };
+    is_path_buf
+        && matches!(
+            structopt_ty,
+            StructOptTy::Other | StructOptTy::Option | StructOptTy::Vec
+        )
+}
+
+// The text of the doc comments of a field
+fn doc_comment(attrs: &[Attribute]) -> String {
+    attrs
+        .iter()
+        .filter(|a| a.path.is_ident("doc"))
+        .filter_map(|a| match a.parse_meta() {
+            Ok(Meta::NameValue(MetaNameValue {
+                lit: Lit::Str(lit), ..
+            })) => Some(String::from(lit.value().trim())),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 #[derive(Clone)]
 pub struct ParsedField {
     ident: Ident,
     structopt_ty: StructOptTy,
     configopt_inner_ty: Ident,
+    ty: Type,
+    configopt_ty: Type,
     span: Span,
     structopt_flatten: bool,
     serde_flatten: bool,
     subcommand: bool,
     positional_vec: bool,
     no_wrap: bool,
+    interpolate_env: bool,
+    relative_to_config: bool,
+    expand_tilde: bool,
     structopt_rename: CasingStyle,
     structopt_name: String,
     serde_name: String,
     to_os_string: Option<Expr>,
+    validators: Vec<Validator>,
+    constraints: Vec<Constraint>,
+    usage: String,
+    aliases: Vec<String>,
+    deprecated: Option<String>,
+    source: FieldSource,
+    doc: String,
 }
 
 impl ParsedField {
@@ -114,8 +194,50 @@ impl ParsedField {
         let no_wrap = configopt_attrs
             .iter()
             .any(|a| matches!(a, ConfigOptAttr::NoWrap));
+        let interpolate_env = configopt_attrs
+            .iter()
+            .any(|a| matches!(a, ConfigOptAttr::InterpolateEnv));
+        let relative_to_config = configopt_attrs
+            .iter()
+            .any(|a| matches!(a, ConfigOptAttr::RelativeToConfig));
+        let expand_tilde = configopt_attrs
+            .iter()
+            .any(|a| matches!(a, ConfigOptAttr::ExpandTilde));
+
+        let aliases = configopt_attrs
+            .iter()
+            .filter_map(|a| match a {
+                ConfigOptAttr::Alias(alias) => Some(alias.value()),
+                _ => None,
+            })
+            .collect();
+        let deprecated = configopt_attrs.iter().find_map(|a| match a {
+            ConfigOptAttr::Deprecated(note) => Some(note.value()),
+            _ => None,
+        });
+
+        let sources = configopt_attrs
+            .iter()
+            .filter_map(|a| match a {
+                ConfigOptAttr::Skip => Some(FieldSource::Skip),
+                ConfigOptAttr::CliOnly => Some(FieldSource::CliOnly),
+                ConfigOptAttr::FileOnly => Some(FieldSource::FileOnly),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let source = match sources.as_slice() {
+            [] => FieldSource::CliAndFile,
+            [source] => *source,
+            _ => panic!("`configopt` only one of `skip`, `cli_only` or `file_only` can be used"),
+        };
+        // Without an argument the doc comment is the only description of a field
+        let doc = doc_comment(&field.attrs);
 
         let structopt_ty = StructOptTy::from_syn_ty(&field.ty);
+        if relative_to_config && !is_path_buf(structopt_ty, &field.ty) {
+            panic!("`configopt` `relative_to_config` can only be used on a `PathBuf`, `Option<PathBuf>` or `Vec<PathBuf>` field");
+        }
+        let original_ty = field.ty.clone();
         let ty = &mut field.ty;
         let inner_ty = inner_ty(ty);
         let configopt_inner_ty = if no_wrap {
@@ -146,7 +268,55 @@ impl ParsedField {
             StructOptAttr::Short | StructOptAttr::Long => false,
             _ => true,
         });
-        let positional_vec = positional && structopt_ty.is_vec();
+        if source != FieldSource::CliAndFile && (structopt_flatten || subcommand) {
+            panic!("`configopt` `skip`, `cli_only` and `file_only` can not be used on a flattened field or subcommand");
+        }
+        // The derive only changes the `configopt` type so the original struct must skip the argument
+        if !source.on_command_line() && !structopt_attrs.contains(&StructOptAttr::Skip) {
+            panic!("`configopt` `skip` and `file_only` require `#[structopt(skip)]` on the field");
+        }
+        let positional_vec = positional && structopt_ty.is_vec() && source.on_command_line();
+        // How clap shows the argument in its errors
+        let usage = if positional {
+            format!("<{}>", structopt_name)
+        } else if structopt_attrs.contains(&StructOptAttr::Long) {
+            format!("--{}", structopt_name)
+        } else {
+            format!("-{}", structopt_name.chars().take(1).collect::<String>())
+        };
+        let usage = if positional || structopt_ty.is_bool() {
+            usage
+        } else {
+            format!("{} <{}>", usage, structopt_name)
+        };
+        // Only arguments on the command line have constraints
+        let mut constraints = if source.on_command_line() {
+            structopt_attrs
+                .iter()
+                .filter_map(|a| match a {
+                    StructOptAttr::Constraint(c) => Some(c.clone()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+        // Like `structopt`, an argument without a natural default is required and a default value
+        // always satisfies `required`
+        let has_default = structopt_attrs
+            .iter()
+            .any(|a| matches!(a, StructOptAttr::DefaultValue | StructOptAttr::FlagParser));
+        if has_default {
+            constraints.retain(|c| *c != Constraint::Required);
+        } else if let StructOptTy::Other = structopt_ty {
+            if source.on_command_line()
+                && !structopt_flatten
+                && !subcommand
+                && !constraints.contains(&Constraint::Required)
+            {
+                constraints.push(Constraint::Required);
+            }
+        }
 
         // The below logic converts the field into a `ConfigOpt` field
 
@@ -154,9 +324,22 @@ impl ParsedField {
         if structopt_flatten || subcommand {
             *inner_ty = configopt_inner_ty.clone();
         }
+        let configopt_ty = ty.clone();
 
         retain_attrs(&mut field.attrs, &retained_attrs);
 
+        // A field that is not an argument or not a config key is skipped by `structopt` or `serde`
+        if !source.on_command_line() {
+            field
+                .attrs
+                .retain(|a| !a.path.is_ident("structopt"));
+            field.attrs.push(parse_quote! {#[structopt(skip)]});
+        }
+        if !source.in_config_files() {
+            field.attrs.retain(|a| !a.path.is_ident("serde"));
+            field.attrs.push(parse_quote! {#[serde(skip)]});
+        }
+
         // If the field is not already, wrap its type in an `Option`. This guarantees that the
         // `ConfigOpt` struct can be parsed regardless of complete CLI input.
         if let StructOptTy::Bool | StructOptTy::Vec | StructOptTy::Other = structopt_ty {
@@ -167,9 +350,11 @@ impl ParsedField {
             // followed by a positional value).
             if structopt_ty.is_bool() {
                 field.ty = parse_quote!(::configopt::ConfigOptBool);
-                field
-                    .attrs
-                    .push(parse_quote! {#[structopt(parse(from_flag = ::configopt::ConfigOptBool::from_flag))]});
+                if source.on_command_line() {
+                    field
+                        .attrs
+                        .push(parse_quote! {#[structopt(parse(from_flag = ::configopt::ConfigOptBool::from_flag))]});
+                }
             }
             // If it was a flattened field all of its fields will be optional so it does not need to
             // be wrapped in an `Option`
@@ -183,6 +368,8 @@ impl ParsedField {
             ident,
             structopt_ty,
             configopt_inner_ty,
+            ty: original_ty,
+            configopt_ty,
             span: field.span(),
             structopt_rename,
             structopt_name,
@@ -195,10 +382,26 @@ impl ParsedField {
             subcommand,
             positional_vec,
             no_wrap,
-            to_os_string: configopt_attrs.into_iter().find_map(|a| match a {
-                ConfigOptAttr::ToOsString(expr) => Some(expr),
+            interpolate_env,
+            relative_to_config,
+            expand_tilde,
+            to_os_string: configopt_attrs.iter().find_map(|a| match a {
+                ConfigOptAttr::ToOsString(expr) => Some(expr.clone()),
                 _ => None,
             }),
+            validators: configopt_attrs
+                .into_iter()
+                .flat_map(|a| match a {
+                    ConfigOptAttr::Validate(validators) => validators,
+                    _ => Vec::new(),
+                })
+                .collect(),
+            constraints,
+            usage,
+            aliases,
+            deprecated,
+            source,
+            doc,
         }
     }
 
@@ -215,6 +418,14 @@ impl ParsedField {
         &self.configopt_inner_ty
     }
 
+    pub fn ty(&self) -> &Type {
+        &self.ty
+    }
+
+    pub fn configopt_ty(&self) -> &Type {
+        &self.configopt_ty
+    }
+
     pub fn is_structopt_flatten(&self) -> bool {
         self.structopt_flatten
     }
@@ -235,6 +446,18 @@ impl ParsedField {
         self.no_wrap
     }
 
+    pub fn interpolate_env(&self) -> bool {
+        self.interpolate_env
+    }
+
+    pub fn relative_to_config(&self) -> bool {
+        self.relative_to_config
+    }
+
+    pub fn expand_tilde(&self) -> bool {
+        self.expand_tilde
+    }
+
     pub fn structopt_rename(&self) -> CasingStyle {
         self.structopt_rename
     }
@@ -250,6 +473,49 @@ impl ParsedField {
     pub fn to_os_string(&self) -> Option<&Expr> {
         self.to_os_string.as_ref()
     }
+
+    pub fn validators(&self) -> &[Validator] {
+        &self.validators
+    }
+
+    pub fn constraints(&self) -> &[Constraint] {
+        &self.constraints
+    }
+
+    pub fn usage(&self) -> &str {
+        &self.usage
+    }
+
+    /// Old names of the field still accepted in config files
+    pub fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+
+    pub fn deprecated(&self) -> Option<&str> {
+        self.deprecated.as_deref()
+    }
+
+    pub fn is_skip(&self) -> bool {
+        self.source == FieldSource::Skip
+    }
+
+    pub fn is_file_only(&self) -> bool {
+        self.source == FieldSource::FileOnly
+    }
+
+    /// Is the field an argument on the command line, it is not `skip` or `file_only`
+    pub fn on_command_line(&self) -> bool {
+        self.source.on_command_line()
+    }
+
+    /// Is the field a key of config files, it is not `skip` or `cli_only`
+    pub fn in_config_files(&self) -> bool {
+        self.source.in_config_files()
+    }
+
+    pub fn doc(&self) -> &str {
+        &self.doc
+    }
 }
 
 impl Spanned for ParsedField {
@@ -291,7 +557,9 @@ pub struct ParsedVariant {
     full_configopt_ident: TokenStream,
     span: Span,
     field_type: FieldType,
+    unnamed_configopt_ty: Option<Type>,
     structopt_name: String,
+    serde_name: String,
 }
 
 impl ParsedVariant {
@@ -314,6 +582,7 @@ impl ParsedVariant {
             serde_rename,
             retained_attrs,
         );
+        let mut unnamed_configopt_ty = None;
         if let Fields::Unnamed(fields) = &mut variant.fields {
             if fields.unnamed.len() > 1 {
                 panic!(
@@ -324,6 +593,7 @@ impl ParsedVariant {
             let field = &mut fields.unnamed[0];
             let ty = inner_ty(&mut field.ty);
             *ty = configopt_ident(ty);
+            unnamed_configopt_ty = Some(field.ty.clone());
         }
 
         Self {
@@ -331,8 +601,11 @@ impl ParsedVariant {
             full_configopt_ident,
             span: variant.span(),
             field_type,
+            unnamed_configopt_ty,
             // TODO: Actually lookup the `structopt` name
             structopt_name: variant_ident.to_string().to_kebab_case(),
+            // TODO: Actually lookup the `serde` name
+            serde_name: variant_ident.to_string(),
         }
     }
 
@@ -348,9 +621,17 @@ impl ParsedVariant {
         &self.field_type
     }
 
+    pub fn unnamed_configopt_ty(&self) -> Option<&Type> {
+        self.unnamed_configopt_ty.as_ref()
+    }
+
     pub fn structopt_name(&self) -> &str {
         &self.structopt_name
     }
+
+    pub fn serde_name(&self) -> &str {
+        &self.serde_name
+    }
 }
 
 impl Spanned for ParsedVariant {
@@ -0,0 +1,117 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parenthesized,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    Attribute, Expr, Ident, LitInt, LitStr, Token,
+};
+
+/// An upgrade of a config file from `#[configopt(upgrade(from => function))]`
+pub struct Upgrade {
+    pub from: u32,
+    pub function: Expr,
+}
+
+impl Parse for Upgrade {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let from: LitInt = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        Ok(Self {
+            from: from.base10_parse()?,
+            function: input.parse()?,
+        })
+    }
+}
+
+/// The version of the config file layout from `#[configopt(version = .., upgrade(..))]`
+pub struct ConfigVersion {
+    pub version: u32,
+    pub upgrades: Vec<Upgrade>,
+}
+
+enum ConfigVersionAttr {
+    Version(u32),
+    Upgrades(Vec<Upgrade>),
+    Other,
+}
+
+impl Parse for ConfigVersionAttr {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let name: Ident = input.parse()?;
+        let name_str = name.to_string();
+
+        if input.peek(Token![=]) {
+            // `name = value` attributes.
+            input.parse::<Token![=]>()?; // skip '='
+            if name_str == "version" {
+                let version: LitInt = input.parse()?;
+                Ok(ConfigVersionAttr::Version(version.base10_parse()?))
+            } else {
+                input.parse::<Expr>()?;
+                Ok(ConfigVersionAttr::Other)
+            }
+        } else if input.peek(syn::token::Paren) {
+            // `name(...)` attributes.
+            let nested;
+            parenthesized!(nested in input);
+            if name_str == "upgrade" {
+                let upgrades = nested.parse_terminated::<_, Token![,]>(Upgrade::parse)?;
+                Ok(ConfigVersionAttr::Upgrades(upgrades.into_iter().collect()))
+            } else {
+                nested.parse::<TokenStream>()?;
+                Ok(ConfigVersionAttr::Other)
+            }
+        } else {
+            // Attributes represented with a sole identifier.
+            Ok(ConfigVersionAttr::Other)
+        }
+    }
+}
+
+// `upgrade(1 => function)` is not valid meta syntax, so these are removed before the other
+// `configopt` attributes are read
+const CONFIGOPT_FIELDS_TO_TRIM: &[&str] = &["version", "upgrade"];
+attribute_trimmer!("configopt", CONFIGOPT_FIELDS_TO_TRIM);
+
+/// Parse and then trim the `version` and `upgrade` attributes of a type
+pub fn parse_and_trim(attrs: &mut [Attribute]) -> Option<ConfigVersion> {
+    let mut version = None;
+    let mut upgrades = Vec::new();
+    for attr in attrs.iter_mut().filter(|a| a.path.is_ident("configopt")) {
+        let parsed = attr
+            .parse_args_with(Punctuated::<ConfigVersionAttr, Token![,]>::parse_terminated)
+            .expect("`configopt` failed to parse `version` or `upgrade`");
+        for a in parsed {
+            match a {
+                ConfigVersionAttr::Version(v) => version = Some(v),
+                ConfigVersionAttr::Upgrades(u) => upgrades.extend(u),
+                ConfigVersionAttr::Other => {}
+            }
+        }
+        trim_attr(attr);
+    }
+    let version = match version {
+        Some(version) => version,
+        None if upgrades.is_empty() => return None,
+        None => panic!("`#[configopt(upgrade(..))]` requires `#[configopt(version = ..)]`"),
+    };
+    if version == 0 {
+        panic!("`#[configopt(version = ..)]` must be at least 1");
+    }
+    for (i, upgrade) in upgrades.iter().enumerate() {
+        if upgrade.from == 0 || upgrade.from >= version {
+            panic!(
+                "`#[configopt(upgrade({} => ..))]` must upgrade from a version before {}",
+                upgrade.from, version
+            );
+        }
+        if upgrades[..i].iter().any(|u| u.from == upgrade.from) {
+            panic!(
+                "`#[configopt(upgrade(..))]` has more than one upgrade from {}",
+                upgrade.from
+            );
+        }
+    }
+    Some(ConfigVersion { version, upgrades })
+}
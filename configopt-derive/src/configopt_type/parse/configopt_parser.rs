@@ -1,14 +1,63 @@
 use syn::{
+    parenthesized,
     parse::{Parse, ParseStream},
     punctuated::Punctuated,
-    Attribute, Expr, Ident, Token,
+    Attribute, Expr, ExprLit, Ident, Lit, LitStr, Token,
 };
 
+/// A check of a field's value from `#[configopt(validate(..))]`
+#[derive(Clone, PartialEq)]
+pub enum Validator {
+    Range(Expr),
+    Regex(LitStr),
+    NonEmpty,
+    PathExists,
+    With(Expr),
+}
+
+impl Parse for Validator {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let name: Ident = input.parse()?;
+        let name_str = name.to_string();
+
+        if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?; // skip '='
+            match name_str.as_ref() {
+                "regex" => Ok(Validator::Regex(input.parse()?)),
+                "with" => Ok(Validator::With(input.parse()?)),
+                s => panic!("`configopt` unrecognized `validate(name = value)` {}", s),
+            }
+        } else if input.peek(syn::token::Paren) {
+            let nested;
+            parenthesized!(nested in input);
+            match name_str.as_ref() {
+                "range" => Ok(Validator::Range(nested.parse()?)),
+                s => panic!("`configopt` unrecognized `validate(name(...))` {}", s),
+            }
+        } else {
+            Ok(match name_str.as_ref() {
+                "non_empty" => Validator::NonEmpty,
+                "path_exists" => Validator::PathExists,
+                s => panic!("`configopt` unrecognized `validate` identifier {}", s),
+            })
+        }
+    }
+}
+
 #[derive(PartialEq)]
 #[allow(clippy::large_enum_variant)]
 pub enum ConfigOptAttr {
     NoWrap,
+    InterpolateEnv,
+    RelativeToConfig,
+    ExpandTilde,
     ToOsString(Expr),
+    Validate(Vec<Validator>),
+    Alias(LitStr),
+    Deprecated(LitStr),
+    Skip,
+    CliOnly,
+    FileOnly,
 }
 
 impl Parse for ConfigOptAttr {
@@ -23,24 +72,47 @@ impl Parse for ConfigOptAttr {
             #[allow(clippy::match_wild_err_arm)]
             match input.parse::<Expr>() {
                 Ok(expr) => {
-                    if name_str == "to_os_string" {
-                        Ok(ConfigOptAttr::ToOsString(expr))
-                    } else {
-                        panic!(
+                    let lit_str = || match &expr {
+                        Expr::Lit(ExprLit {
+                            lit: Lit::Str(lit), ..
+                        }) => lit.clone(),
+                        _ => panic!("`configopt` expected a string literal for {}", name_str),
+                    };
+                    match name_str.as_ref() {
+                        "to_os_string" => Ok(ConfigOptAttr::ToOsString(expr)),
+                        "alias" => Ok(ConfigOptAttr::Alias(lit_str())),
+                        "deprecated" => Ok(ConfigOptAttr::Deprecated(lit_str())),
+                        _ => panic!(
                             "`configopt` unrecognized `name = value` attribute {}",
                             name_str
-                        );
+                        ),
                     }
                 }
                 Err(_) => panic!("`configopt` parsing `structopt` expected `expression` after `=`"),
             }
         } else if input.peek(syn::token::Paren) {
             // `name(...)` attributes.
-            panic!("`configopt` does not have any `name(...)` attributes")
+            let nested;
+            parenthesized!(nested in input);
+            match name_str.as_ref() {
+                "validate" => Ok(ConfigOptAttr::Validate(
+                    nested
+                        .parse_terminated::<_, Token![,]>(Validator::parse)?
+                        .into_iter()
+                        .collect(),
+                )),
+                s => panic!("`configopt` unrecognized `name(...)` attribute {}", s),
+            }
         } else {
             // Attributes represented with a sole identifier.
             Ok(match name_str.as_ref() {
                 "nowrap" => ConfigOptAttr::NoWrap,
+                "interpolate_env" => ConfigOptAttr::InterpolateEnv,
+                "relative_to_config" => ConfigOptAttr::RelativeToConfig,
+                "expand_tilde" => ConfigOptAttr::ExpandTilde,
+                "skip" => ConfigOptAttr::Skip,
+                "cli_only" => ConfigOptAttr::CliOnly,
+                "file_only" => ConfigOptAttr::FileOnly,
                 s => panic!("`configopt` unrecognized sole identifier attribute {}", s),
             })
         }
@@ -1,11 +1,11 @@
 use super::CasingStyle;
-use proc_macro2::TokenStream;
-use quote::quote;
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{quote, ToTokens};
 use syn::{
     parenthesized,
     parse::{Parse, ParseStream},
     punctuated::Punctuated,
-    Attribute, Expr, GenericArgument, Ident, LitStr, Path, PathArguments,
+    Attribute, Expr, ExprLit, GenericArgument, Ident, Lit, LitStr, Path, PathArguments,
     PathArguments::AngleBracketed,
     PathSegment, Token, Type, TypePath,
 };
@@ -18,10 +18,74 @@ pub enum StructOptAttr {
     NameLitStr(String),
     Flatten,
     Subcommand,
+    /// `skip` or `skip = value`, the field is not an argument
+    Skip,
+    /// `default_value` or `default_value_os`
+    DefaultValue,
+    /// `parse(from_occurrences)` or `parse(from_flag)`, the argument is never required
+    FlagParser,
+    Constraint(Constraint),
     // We only care about some of the structopt attributes
     Unknown,
 }
 
+/// A relationship between arguments. These are trimmed from the `configopt` type so they are
+/// checked after the config files are merged instead.
+#[derive(Clone, PartialEq)]
+pub enum Constraint {
+    Required,
+    Requires(Vec<String>),
+    ConflictsWith(Vec<String>),
+    RequiredUnlessAny(Vec<String>),
+    RequiredUnlessAll(Vec<String>),
+    /// Pairs of an argument name and a value
+    RequiredIfAny(Vec<(String, String)>),
+    /// Pairs of a value of this argument and the argument it requires
+    RequiresIf(Vec<(String, String)>),
+}
+
+fn pairs(values: Vec<String>) -> Vec<(String, String)> {
+    values
+        .chunks(2)
+        .filter(|c| c.len() == 2)
+        .map(|c| (c[0].clone(), c[1].clone()))
+        .collect()
+}
+
+impl Constraint {
+    /// `values` are the string literals given to the attribute.
+    fn new(name: &str, enabled: bool, values: Vec<String>) -> Option<Self> {
+        Some(match name {
+            "required" if enabled => Self::Required,
+            "requires" | "requires_all" => Self::Requires(values),
+            "conflicts_with" | "conflicts_with_all" => Self::ConflictsWith(values),
+            "required_unless" | "required_unless_one" => Self::RequiredUnlessAny(values),
+            "required_unless_all" => Self::RequiredUnlessAll(values),
+            "required_if" | "required_ifs" => Self::RequiredIfAny(pairs(values)),
+            "requires_if" | "requires_ifs" => Self::RequiresIf(pairs(values)),
+            _ => return None,
+        })
+    }
+
+    fn attr(name: &str, enabled: bool, values: Vec<String>) -> StructOptAttr {
+        Self::new(name, enabled, values).map_or(StructOptAttr::Unknown, StructOptAttr::Constraint)
+    }
+}
+
+// Every string literal in `tokens`, like the argument names of `conflicts_with_all = &["a", "b"]`
+fn string_literals(tokens: TokenStream) -> Vec<String> {
+    tokens
+        .into_iter()
+        .flat_map(|tree| match tree {
+            TokenTree::Group(group) => string_literals(group.stream()),
+            TokenTree::Literal(lit) => syn::parse2::<LitStr>(lit.into_token_stream())
+                .map(|lit| vec![lit.value()])
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
 impl Parse for StructOptAttr {
     fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
         let name: Ident = input.parse()?;
@@ -42,29 +106,48 @@ impl Parse for StructOptAttr {
                         lit_str.parse().expect("infallible parse"),
                     )),
                     "name" => Ok(StructOptAttr::NameLitStr(lit_str)),
-                    _ => Ok(StructOptAttr::Unknown),
+                    "default_value" | "default_value_os" => Ok(StructOptAttr::DefaultValue),
+                    "skip" => Ok(StructOptAttr::Skip),
+                    _ => Ok(Constraint::attr(&name_str, true, vec![lit_str])),
                 }
             } else {
                 match input.parse::<Expr>() {
-                    Ok(_) => {
+                    Ok(expr) => {
                         if name_str == "name" {
                             // TODO
                             panic!("`configopt` parsing `structopt` only supports string literal for argument name")
                         }
+                        if name_str == "default_value" || name_str == "default_value_os" {
+                            return Ok(StructOptAttr::DefaultValue);
+                        }
+                        if name_str == "skip" {
+                            return Ok(StructOptAttr::Skip);
+                        }
+                        // Like `required = false`
+                        let enabled = !matches!(&expr, Expr::Lit(ExprLit { lit: Lit::Bool(b), .. }) if !b.value);
+                        Ok(Constraint::attr(
+                            &name_str,
+                            enabled,
+                            string_literals(expr.into_token_stream()),
+                        ))
                     }
                     Err(e) => {
                         panic!("`configopt` parsing `structopt` expected `string literal` or `expression` after `=`, err: {}", e)
                     }
                 }
-                Ok(StructOptAttr::Unknown)
             }
         } else if input.peek(syn::token::Paren) {
             // `name(...)` attributes.
             let nested;
-            // Even though we do not do anything here we still need to consume the tokens from the ParseStream
             parenthesized!(nested in input);
-            nested.parse::<TokenStream>()?;
-            Ok(StructOptAttr::Unknown)
+            let tokens = nested.parse::<TokenStream>()?;
+            if name_str == "parse" {
+                let parser = tokens.clone().into_iter().next().map(|t| t.to_string());
+                if let Some("from_occurrences") | Some("from_flag") = parser.as_deref() {
+                    return Ok(StructOptAttr::FlagParser);
+                }
+            }
+            Ok(Constraint::attr(&name_str, true, string_literals(tokens)))
         } else {
             // Attributes represented with a sole identifier.
             Ok(match name_str.as_ref() {
@@ -72,7 +155,8 @@ impl Parse for StructOptAttr {
                 "short" => StructOptAttr::Short,
                 "flatten" => StructOptAttr::Flatten,
                 "subcommand" => StructOptAttr::Subcommand,
-                _ => StructOptAttr::Unknown,
+                "skip" => StructOptAttr::Skip,
+                _ => Constraint::attr(&name_str, true, Vec::new()),
             })
         }
     }
@@ -92,10 +176,13 @@ pub fn parse_attrs(attrs: &[Attribute]) -> Vec<StructOptAttr> {
 /// These are `structopt` attributes that do not make sense to apply to the `configopt` type. The
 /// purpose for trimming these is to remove all restrictions on parsing the `configopt` type from
 /// the CLI. This gives us the chance to read values from config files or other sources before we
-/// encounter CLI parsing errors.
+/// encounter CLI parsing errors. Without default values the `configopt` type only holds the values
+/// set on the command line, so config files can override the defaults.
 const STRUCTOPT_FIELDS_TO_TRIM: &[&str] = &[
     "conflicts_with",
     "conflicts_with_all",
+    "default_value",
+    "default_value_os",
     "required",
     "required_if",
     "required_ifs",
@@ -156,6 +243,14 @@ impl StructOptTy {
     }
 }
 
+/// Strip any `Option` and `Vec` wrappers to get the type of a single value
+pub fn value_ty(mut ty: &syn::Type) -> &syn::Type {
+    while let Some(subty) = subty_if_name(ty, "Option").or_else(|| subty_if_name(ty, "Vec")) {
+        ty = subty;
+    }
+    ty
+}
+
 fn only_last_segment(ty: &syn::Type) -> Option<&PathSegment> {
     match ty {
         Type::Path(TypePath {
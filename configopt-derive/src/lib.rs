@@ -53,6 +53,14 @@ pub fn configopt_fields(
         #[structopt(long = "generate-config", hidden = #hidden)]
         #[serde(skip)]
         generate_config: bool,
+        /// Check the config is valid and complete, reporting every problem
+        #[structopt(long = "check-config", hidden = #hidden)]
+        #[serde(skip)]
+        check_config: bool,
+        /// Print the value of each argument and where it came from
+        #[structopt(long = "explain-config", hidden = #hidden)]
+        #[serde(skip)]
+        explain_config: bool,
     });
     ast.append_named(additional_fields);
 
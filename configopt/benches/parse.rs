@@ -0,0 +1,81 @@
+//! Compare plain structopt parsing with `try_from_iter_with_configopt` for an app with a
+//! subcommand tree. Run with `cargo bench -p configopt --bench parse`.
+
+use configopt::{configopt_fields, ConfigOpt};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize)]
+#[configopt(derive(Debug, Serialize), attrs(serde))]
+struct Shared {
+    #[structopt(long)]
+    host: Option<String>,
+    #[structopt(long, default_value = "8080")]
+    port: u16,
+    #[structopt(long)]
+    verbose: bool,
+    #[structopt(long)]
+    tags: Vec<String>,
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize)]
+#[configopt(derive(Debug, Serialize), attrs(serde))]
+struct Service {
+    #[structopt(long)]
+    name: String,
+    #[structopt(flatten)]
+    #[serde(flatten)]
+    shared: Shared,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug, Serialize))]
+enum Command {
+    Start(Service),
+    Stop(Service),
+    Restart(Service),
+    Status(Service),
+    Reload(Service),
+    Logs(Service),
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug, Serialize), attrs(serde))]
+struct App {
+    #[structopt(flatten)]
+    shared: Shared,
+    #[structopt(subcommand)]
+    command: Command,
+}
+
+const ARGS: &[&str] = &[
+    "app",
+    "--host",
+    "localhost",
+    "--verbose",
+    "logs",
+    "--name",
+    "web",
+    "--port",
+    "80",
+    "--tags",
+    "a",
+    "b",
+    "c",
+];
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    group.bench_function("structopt", |b| {
+        b.iter(|| App::from_iter_safe(black_box(ARGS)).unwrap())
+    });
+    group.bench_function("configopt", |b| {
+        b.iter(|| App::try_from_iter_with_configopt(black_box(ARGS)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
@@ -7,7 +7,7 @@ use structopt::StructOpt;
 #[configopt(derive(Debug, PartialEq), attrs(serde))]
 #[serde(deny_unknown_fields)]
 struct MyStruct {
-    #[structopt(long, default_value = "true")]
+    #[structopt(long)]
     maybe: Option<bool>,
 }
 fn main() {}
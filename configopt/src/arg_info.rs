@@ -0,0 +1,104 @@
+use structopt::clap::{App, ArgSettings};
+
+/// Information about an argument of a `clap::App`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ArgInfo {
+    /// The long help if set, otherwise the short help
+    pub help: String,
+    pub hidden: bool,
+    pub default_value: Option<String>,
+    pub long: Option<String>,
+    pub short: Option<char>,
+    pub env: Option<String>,
+    pub possible_values: Vec<String>,
+    /// Is this a flag that does not take a value
+    pub is_flag: bool,
+    /// Can the argument occur more than once
+    pub is_multiple: bool,
+    pub is_positional: bool,
+}
+
+fn help(help: Option<&str>, long_help: Option<&str>) -> String {
+    String::from(long_help.unwrap_or_else(|| help.unwrap_or("")))
+}
+
+/// Lookup the argument `name` in `app`
+pub fn arg_info(app: &App<'_, '_>, name: &str) -> Option<ArgInfo> {
+    if let Some(arg) = app.p.flags.iter().find(|a| a.b.name == name) {
+        return Some(ArgInfo {
+            help: help(arg.b.help, arg.b.long_help),
+            hidden: arg.b.is_set(ArgSettings::Hidden),
+            long: arg.s.long.map(String::from),
+            short: arg.s.short,
+            is_flag: true,
+            is_multiple: arg.b.is_set(ArgSettings::Multiple),
+            ..ArgInfo::default()
+        });
+    }
+    if let Some(arg) = app.p.opts.iter().find(|a| a.b.name == name) {
+        return Some(ArgInfo {
+            help: help(arg.b.help, arg.b.long_help),
+            hidden: arg.b.is_set(ArgSettings::Hidden),
+            default_value: arg.v.default_val.map(|v| v.to_string_lossy().into_owned()),
+            long: arg.s.long.map(String::from),
+            short: arg.s.short,
+            env: arg.v.env.as_ref().map(|(e, _)| e.to_string_lossy().into_owned()),
+            possible_values: arg
+                .v
+                .possible_vals
+                .iter()
+                .flatten()
+                .map(|v| String::from(*v))
+                .collect(),
+            is_multiple: arg.b.is_set(ArgSettings::Multiple),
+            ..ArgInfo::default()
+        });
+    }
+    if let Some((_, arg)) = app.p.positionals.iter().find(|(_, a)| a.b.name == name) {
+        return Some(ArgInfo {
+            help: help(arg.b.help, arg.b.long_help),
+            hidden: arg.b.is_set(ArgSettings::Hidden),
+            default_value: arg.v.default_val.map(|v| v.to_string_lossy().into_owned()),
+            env: arg.v.env.as_ref().map(|(e, _)| e.to_string_lossy().into_owned()),
+            possible_values: arg
+                .v
+                .possible_vals
+                .iter()
+                .flatten()
+                .map(|v| String::from(*v))
+                .collect(),
+            is_multiple: arg.b.is_set(ArgSettings::Multiple),
+            is_positional: true,
+            ..ArgInfo::default()
+        });
+    }
+    None
+}
+
+/// Lookup the subcommand `name` in `app`
+pub fn subcommand_app<'a, 'b, 'c>(app: &'c App<'a, 'b>, name: &str) -> Option<&'c App<'a, 'b>> {
+    app.p.subcommands.iter().find(|s| s.p.meta.name == name)
+}
+
+/// Get the names of the subcommands of `app`
+pub fn subcommand_names(app: &App<'_, '_>) -> Vec<String> {
+    app.p
+        .subcommands
+        .iter()
+        .map(|s| s.p.meta.name.clone())
+        .collect()
+}
+
+/// Get the name and information of every argument of `app` in the order clap lists them
+pub fn app_args(app: &App<'_, '_>) -> Vec<(String, ArgInfo)> {
+    let names = app
+        .p
+        .flags
+        .iter()
+        .map(|a| a.b.name)
+        .chain(app.p.opts.iter().map(|a| a.b.name))
+        .chain(app.p.positionals.values().map(|a| a.b.name));
+    names
+        .filter_map(|name| arg_info(app, name).map(|info| (String::from(name), info)))
+        .collect()
+}
@@ -0,0 +1,389 @@
+use crate::{
+    arg_info::{app_args, subcommand_app, subcommand_names, ArgInfo},
+    command_line,
+    dotted_key::scalar,
+    parse_config, read_config, ConfigFormat, ConfigOptArgToOsString, Error, Result,
+};
+use serde::de::DeserializeOwned;
+use std::{
+    env,
+    ffi::OsString,
+    io::{Error as IoError, ErrorKind as IoErrorKind},
+    path::PathBuf,
+};
+use structopt::clap::{App, Arg, ArgMatches};
+use toml::{value::Table, Value};
+
+/// A source of config values for a [`ConfigOptBuilder`](struct.ConfigOptBuilder.html)
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigSource {
+    /// A config file. A path of `-` reads from stdin.
+    File(PathBuf),
+    /// Environment variables starting with the prefix followed by `_`
+    ///
+    /// The rest of the variable name is lowercased to get the key, `__` separates the name of a
+    /// subcommand from its keys. For example with the prefix `APP`, `APP_LOG_LEVEL` sets
+    /// `log_level` and `APP_RUN__THREADS` sets `run.threads`.
+    Env(String),
+    /// A config document
+    Inline(ConfigFormat, String),
+}
+
+/// The result of [`ConfigOptBuilder`](struct.ConfigOptBuilder.html) parsing a command line
+#[derive(Debug)]
+pub struct ConfigOptMatches<'a, T> {
+    /// The matches of the app with the config values as argument defaults
+    pub matches: ArgMatches<'a>,
+    /// The config sources merged with the values set on the command line
+    pub value: Value,
+    /// `value` deserialized
+    pub config: T,
+}
+
+/// Layer config sources under the command line of a `clap::App` built at runtime
+///
+/// This gives an app built without `#[derive(ConfigOpt)]` the same behavior. Later sources take
+/// precedence over earlier ones and the command line takes precedence over all of them. Keys are
+/// the argument names with `-` replaced by `_`, the keys of a subcommand are nested in a table
+/// named after it.
+pub struct ConfigOptBuilder<'a, 'b> {
+    app: App<'a, 'b>,
+    sources: Vec<ConfigSource>,
+}
+
+impl<'a, 'b> ConfigOptBuilder<'a, 'b> {
+    pub fn new(app: App<'a, 'b>) -> Self {
+        Self {
+            app,
+            sources: Vec::new(),
+        }
+    }
+
+    /// Add a source of config values
+    pub fn source(mut self, source: ConfigSource) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    /// Add a config file
+    pub fn file(self, path: impl Into<PathBuf>) -> Self {
+        self.source(ConfigSource::File(path.into()))
+    }
+
+    /// Add the environment variables starting with `prefix`
+    pub fn env(self, prefix: impl Into<String>) -> Self {
+        self.source(ConfigSource::Env(prefix.into()))
+    }
+
+    /// Add a config document
+    pub fn inline(self, format: ConfigFormat, contents: impl Into<String>) -> Self {
+        self.source(ConfigSource::Inline(format, contents.into()))
+    }
+
+    /// Parse the command line arguments, taking into account the config sources
+    pub fn try_from_args<T: DeserializeOwned>(self) -> Result<ConfigOptMatches<'a, T>> {
+        self.try_from_iter(env::args())
+    }
+
+    /// Parse any iterator such as a Vec of your making, taking into account the config sources
+    pub fn try_from_iter<T, I>(self, iter: I) -> Result<ConfigOptMatches<'a, T>>
+    where
+        T: DeserializeOwned,
+        I: IntoIterator,
+        I::Item: Into<OsString> + Clone,
+    {
+        let mut table = Table::new();
+        for source in &self.sources {
+            merge(&mut table, load(source)?);
+        }
+        let defaults = Value::Table(table);
+        let mut app = self.app;
+        let iter = iter.into_iter().map(Into::into).collect::<Vec<_>>();
+        let app_args = AppArgs::new(app.get_name(), &app);
+        let matches = match app_args.tolerant_app().get_matches_from_safe(&iter) {
+            // The values missing from the command line are filled in from the config sources
+            Ok(matches) => {
+                let iter = command_line::with_defaults(&app, &iter, &matches, &defaults);
+                app.get_matches_from_safe_borrow(iter)?
+            }
+            // Get the help or error using the actual app
+            Err(_) => app.get_matches_from_safe_borrow(iter)?,
+        };
+        let mut table = match defaults {
+            Value::Table(table) => table,
+            _ => unreachable!("defaults are a table"),
+        };
+        set_from_matches(&app, &matches, &mut table);
+        let value = Value::Table(table);
+        let config = value.clone().try_into().map_err(|e: toml::de::Error| {
+            Error::ConfigStr(IoError::new(IoErrorKind::InvalidData, e))
+        })?;
+        Ok(ConfigOptMatches {
+            matches,
+            value,
+            config,
+        })
+    }
+}
+
+// The arguments and subcommands of an app
+struct AppArgs {
+    name: String,
+    args: Vec<(String, ArgInfo)>,
+    subcommands: Vec<AppArgs>,
+}
+
+impl AppArgs {
+    fn new(name: &str, app: &App<'_, '_>) -> Self {
+        let subcommands = subcommand_names(app)
+            .iter()
+            .filter_map(|name| subcommand_app(app, name).map(|app| Self::new(name, app)))
+            .collect();
+        Self {
+            name: String::from(name),
+            args: app_args(app),
+            subcommands,
+        }
+    }
+
+    // An app with the same arguments but without required arguments, default values or any
+    // validation. It only tells which values were set on the command line.
+    fn tolerant_app(&self) -> App<'_, '_> {
+        let args = self.args.iter().map(|(name, info)| {
+            let mut arg = Arg::with_name(name).multiple(info.is_multiple);
+            if let Some(long) = &info.long {
+                arg = arg.long(long);
+            }
+            if let Some(short) = info.short {
+                arg = arg.short(short.to_string());
+            }
+            if !info.is_flag && !info.is_positional {
+                arg = arg.takes_value(true).min_values(0);
+            }
+            arg
+        });
+        let subcommands = self.subcommands.iter().map(Self::tolerant_app);
+        App::new(self.name.as_str())
+            .args(&args.collect::<Vec<_>>())
+            .subcommands(subcommands)
+    }
+}
+
+fn load(source: &ConfigSource) -> Result<Table> {
+    match source {
+        ConfigSource::File(path) => {
+            let contents = read_config(path).map_err(|e| Error::ConfigFile(path.clone(), e))?;
+            parse_config(ConfigFormat::Toml, &contents)
+                .map_err(|e| Error::ConfigFile(path.clone(), e))
+        }
+        ConfigSource::Env(prefix) => Ok(from_env(prefix, env::vars_os())),
+        ConfigSource::Inline(format, contents) => {
+            parse_config(*format, contents).map_err(Error::ConfigStr)
+        }
+    }
+}
+
+fn from_env(prefix: &str, vars: impl Iterator<Item = (OsString, OsString)>) -> Table {
+    let prefix = format!("{}_", prefix);
+    let mut table = Table::new();
+    'vars: for (name, value) in vars {
+        let (name, value) = match (name.to_str(), value.to_str()) {
+            (Some(name), Some(value)) if name.starts_with(&prefix) => (name, value),
+            _ => continue,
+        };
+        let path = name[prefix.len()..]
+            .to_lowercase()
+            .split("__")
+            .map(String::from)
+            .collect::<Vec<_>>();
+        if path.iter().any(String::is_empty) {
+            continue;
+        }
+        let (key, tables) = path
+            .split_last()
+            .expect("split to return at least one item");
+        let mut current = &mut table;
+        for name in tables {
+            current = match current
+                .entry(name.clone())
+                .or_insert_with(|| Value::Table(Table::new()))
+            {
+                Value::Table(table) => table,
+                _ => continue 'vars,
+            };
+        }
+        current.insert(key.clone(), scalar(value));
+    }
+    table
+}
+
+// Merge `other` into `table`, tables are merged recursively and any other value replaced
+fn merge(table: &mut Table, other: Table) {
+    for (key, value) in other {
+        match (table.get_mut(&key), value) {
+            (Some(Value::Table(existing)), Value::Table(value)) => merge(existing, value),
+            (_, value) => {
+                table.insert(key, value);
+            }
+        }
+    }
+}
+
+// The key of an argument or subcommand in `table`, preferring an exact match
+fn key(table: &Table, name: &str) -> String {
+    if table.contains_key(name) {
+        String::from(name)
+    } else {
+        name.replace('-', "_")
+    }
+}
+
+impl ConfigOptArgToOsString for Value {
+    fn arg_to_os_string(&self, arg_path: &[String]) -> Option<OsString> {
+        let mut value = self;
+        for name in arg_path {
+            let table = value.as_table()?;
+            value = table.get(&key(table, name))?;
+        }
+        match value {
+            Value::String(s) => Some(s.into()),
+            Value::Integer(_) | Value::Float(_) | Value::Boolean(_) | Value::Datetime(_) => {
+                Some(value.to_string().into())
+            }
+            Value::Array(_) | Value::Table(_) => None,
+        }
+    }
+}
+
+// Set the values of the command line in `table`. Values the user set override the config, default
+// values only fill in missing keys.
+fn set_from_matches(app: &App<'_, '_>, matches: &ArgMatches<'_>, table: &mut Table) {
+    for (name, info) in app_args(app) {
+        if !matches.is_present(&name) {
+            continue;
+        }
+        let key = key(table, &name);
+        if matches.occurrences_of(&name) == 0 && table.contains_key(&key) {
+            continue;
+        }
+        let value = if info.is_flag {
+            if info.is_multiple {
+                Value::Integer(matches.occurrences_of(&name) as i64)
+            } else {
+                Value::Boolean(true)
+            }
+        } else {
+            let values = matches
+                .values_of_lossy(&name)
+                .unwrap_or_default()
+                .iter()
+                .map(|v| scalar(v))
+                .collect::<Vec<_>>();
+            if info.is_multiple {
+                Value::Array(values)
+            } else {
+                match values.into_iter().next() {
+                    Some(value) => value,
+                    None => continue,
+                }
+            }
+        };
+        table.insert(key, value);
+    }
+    if let (name, Some(sub_matches)) = matches.subcommand() {
+        if let Some(sub_app) = subcommand_app(app, name) {
+            let key = key(table, name);
+            let entry = table
+                .entry(key)
+                .or_insert_with(|| Value::Table(Table::new()));
+            if !entry.is_table() {
+                *entry = Value::Table(Table::new());
+            }
+            if let Value::Table(sub_table) = entry {
+                set_from_matches(sub_app, sub_matches, sub_table);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_source() {
+        let vars = vec![
+            ("APP_PORT", "8080"),
+            ("APP_LOG_LEVEL", "debug"),
+            ("APP_RUN__THREADS", "4"),
+            ("APP_RUN__ENABLED", "true"),
+            ("OTHER_PORT", "1"),
+            ("APP_BAD__", "1"),
+        ];
+        let vars = vars
+            .into_iter()
+            .map(|(n, v)| (OsString::from(n), OsString::from(v)));
+        let table = from_env("APP", vars);
+        let expected = toml::toml! {
+            port = 8080
+            log_level = "debug"
+            [run]
+            threads = 4
+            enabled = true
+        };
+        assert_eq!(expected, Value::Table(table));
+    }
+
+    #[test]
+    fn merge_tables() {
+        let mut table = toml::toml! {
+            port = 80
+            [run]
+            threads = 1
+            name = "a"
+        }
+        .try_into::<Table>()
+        .unwrap();
+        let other = toml::toml! {
+            port = 8080
+            [run]
+            threads = 4
+        }
+        .try_into::<Table>()
+        .unwrap();
+        merge(&mut table, other);
+        let expected = toml::toml! {
+            port = 8080
+            [run]
+            threads = 4
+            name = "a"
+        };
+        assert_eq!(expected, Value::Table(table));
+    }
+
+    #[test]
+    fn value_to_os_string() {
+        let value = toml::toml! {
+            log_level = "debug"
+            port = 8080
+            list = [1, 2]
+            [run]
+            threads = 4
+        };
+        let path = |p: &[&str]| p.iter().map(|s| String::from(*s)).collect::<Vec<_>>();
+        assert_eq!(
+            Some(OsString::from("debug")),
+            value.arg_to_os_string(&path(&["log-level"]))
+        );
+        assert_eq!(
+            Some(OsString::from("8080")),
+            value.arg_to_os_string(&path(&["port"]))
+        );
+        assert_eq!(
+            Some(OsString::from("4")),
+            value.arg_to_os_string(&path(&["run", "threads"]))
+        );
+        assert_eq!(None, value.arg_to_os_string(&path(&["list"])));
+        assert_eq!(None, value.arg_to_os_string(&path(&["missing"])));
+    }
+}
@@ -0,0 +1,103 @@
+use crate::{constraints, validate, ConfigOpt, ConfigOptType, Error, Result};
+use std::{ffi::OsString, path::Path};
+use structopt::{
+    clap::{ArgMatches, ErrorKind as ClapErrorKind},
+    StructOpt,
+};
+use toml::Value;
+
+/// Check the keys and values of a config file against `configopt`, recording each problem
+///
+/// Every value with a known key is set in `configopt`, so after checking all config files it holds
+/// the merged config.
+#[doc(hidden)]
+pub fn check_config_file(
+    configopt: &mut impl ConfigOptType,
+    path: &Path,
+    value: Result<Value>,
+    problems: &mut Vec<String>,
+) {
+    let table = match value {
+        Ok(Value::Table(table)) => table,
+        Ok(_) => return,
+        // Like when reading config files, missing files are skipped
+        Err(e) if e.config_file_not_found() => return,
+        Err(e) => {
+            problems.push(e.to_string());
+            return;
+        }
+    };
+    for (name, value) in &table {
+        check_key(configopt, path, &[], name, value, problems);
+    }
+}
+
+// A table is descended into if any of its keys are known, otherwise it is the value of a key
+fn check_key<'a>(
+    configopt: &mut impl ConfigOptType,
+    file: &Path,
+    path: &[&'a str],
+    name: &'a str,
+    value: &'a Value,
+    problems: &mut Vec<String>,
+) {
+    let mut path = path.to_vec();
+    path.push(name);
+    if let Value::Table(table) = value {
+        let has_known_key = table.keys().any(|k| {
+            let mut path = path.clone();
+            path.push(k);
+            configopt.get_with_path(&path).is_some()
+        });
+        if has_known_key {
+            for (name, value) in table {
+                check_key(configopt, file, &path, name, value, problems);
+            }
+            return;
+        }
+    }
+    let key = path.join(".");
+    let e = match configopt.set_with_path(&path, value.clone()) {
+        Some(Ok(())) => return,
+        Some(Err(e)) => Error::InvalidValue(key, e),
+        None => Error::UnknownKey(key),
+    };
+    problems.push(format!("{} in file '{}'", e, file.display()));
+}
+
+/// Check every layer of the config and return the problems found as an error
+pub(crate) fn check_config<T: ConfigOpt>(
+    iter: &[OsString],
+    matches: &ArgMatches<'_>,
+    mut configopt: T::ConfigOptType,
+) -> Error {
+    let mut problems = Vec::new();
+    configopt.check_config_files(&mut problems);
+    // Validators and constraints run on every layer merged the same way as when parsing
+    let from_cli = T::ConfigOptType::from_clap(matches);
+    let mut merged = T::ConfigOptType::from_clap(matches);
+    let mut files = Vec::new();
+    if merged.patch_with_config_files_recording(&mut files).is_ok() {
+        if let Err(Error::Validation(errors)) = validate::validate(&merged, Some(&from_cli), &files)
+        {
+            problems.extend(errors.iter().map(ToString::to_string));
+        }
+        problems.extend(constraints::problems(&merged, Some(&from_cli), &files));
+    }
+    // Invalid values are left out of `configopt`, so it is complete even if `merged` is not
+    for usage in constraints::missing(&configopt) {
+        // A missing argument may already be reported with the constraint requiring it
+        let problem = format!("Missing required value for '{}'", usage);
+        if !problems.contains(&problem) {
+            problems.push(problem);
+        }
+    }
+    // Anything else the actual app rejects, like a missing subcommand
+    if let Err(e) = T::try_from_iter_with_defaults(iter, &configopt) {
+        if e.kind != ClapErrorKind::MissingRequiredArgument {
+            problems.push(e.message);
+        }
+    }
+    Error::ConfigCheck(problems)
+}
+
@@ -0,0 +1,105 @@
+//! Fill in the default values of an app by rebuilding its command line
+//!
+//! clap 2 has no public API to change the arguments of a built `App`. Instead the command line is
+//! first parsed by an app without any required arguments or default values, which tells what the
+//! user set. The values it did not set are then added to the command line and the actual app parses
+//! the result, so clap still validates every value and fills in its own defaults.
+
+use crate::{
+    arg_info::{app_args, subcommand_app},
+    ConfigOptArgToOsString,
+};
+use std::{
+    env,
+    ffi::{OsStr, OsString},
+};
+use structopt::clap::{App, ArgMatches};
+
+/// Build a command line for `app` from the `matches` of the command line `args`
+///
+/// Arguments in `matches` keep the values set on the command line. Any other option or positional
+/// argument gets its value from `defaults`, unless its environment variable is set.
+pub(crate) fn with_defaults(
+    app: &App<'_, '_>,
+    args: &[OsString],
+    matches: &ArgMatches<'_>,
+    defaults: &impl ConfigOptArgToOsString,
+) -> Vec<OsString> {
+    let mut command_line = args.iter().take(1).cloned().collect();
+    let mut arg_path = Vec::new();
+    push_args(app, matches, defaults, &mut arg_path, &mut command_line);
+    command_line
+}
+
+fn push_args(
+    app: &App<'_, '_>,
+    matches: &ArgMatches<'_>,
+    defaults: &impl ConfigOptArgToOsString,
+    arg_path: &mut Vec<String>,
+    command_line: &mut Vec<OsString>,
+) {
+    let mut positionals = Vec::new();
+    for (name, info) in app_args(app) {
+        let occurrences = matches.occurrences_of(&name);
+        let values = if occurrences > 0 {
+            matches
+                .values_of_os(&name)
+                .map(|values| values.map(OsStr::to_os_string).collect())
+                .unwrap_or_default()
+        } else if info.is_flag || info.env.is_some_and(|e| env::var_os(e).is_some()) {
+            // A flag has no default value and clap reads the environment variable itself
+            Vec::new()
+        } else {
+            arg_path.push(name);
+            let default = defaults.arg_to_os_string(arg_path);
+            arg_path.pop();
+            default.into_iter().collect()
+        };
+        if info.is_positional {
+            positionals.extend(values);
+            continue;
+        }
+        if values.is_empty() && occurrences == 0 {
+            continue;
+        }
+        let switch = match (info.long, info.short) {
+            (Some(long), _) => format!("--{}", long),
+            (None, Some(short)) => format!("-{}", short),
+            (None, None) => continue,
+        };
+        if info.is_flag || (values.is_empty() && occurrences > 0) {
+            // A flag, or an option whose value is optional, set without a value
+            for _ in 0..occurrences {
+                command_line.push(OsString::from(&switch));
+            }
+        } else if info.is_multiple || values.len() == 1 {
+            // With `=` a value can start with `-` and only one value is taken
+            for value in values {
+                let mut arg = OsString::from(format!("{}=", switch));
+                arg.push(value);
+                command_line.push(arg);
+            }
+        } else {
+            command_line.push(OsString::from(switch));
+            command_line.extend(values);
+        }
+    }
+    let (name, sub_matches) = matches.subcommand();
+    let subcommand = sub_matches
+        .and_then(|sub_matches| subcommand_app(app, name).map(|sub_app| (sub_app, sub_matches)));
+    match subcommand {
+        Some((sub_app, sub_matches)) => {
+            command_line.extend(positionals);
+            command_line.push(OsString::from(name));
+            arg_path.push(String::from(name));
+            push_args(sub_app, sub_matches, defaults, arg_path, command_line);
+            arg_path.pop();
+        }
+        // Every value after `--` is positional, even one starting with `-`
+        None if !positionals.is_empty() => {
+            command_line.push(OsString::from("--"));
+            command_line.extend(positionals);
+        }
+        None => {}
+    }
+}
@@ -0,0 +1,219 @@
+//! Shell completions that know about config files
+use crate::{
+    arg_info::arg_info,
+    config_command::{ConfigCommand, CONFIG_COMMAND_NAME, KEY_HELP},
+    ConfigFormat, ConfigOptType,
+};
+use std::io::{self, Write};
+use structopt::clap::App;
+
+pub use structopt::clap::Shell;
+
+// The subcommands of the `config` subcommand taking a key
+const KEY_COMMANDS: &[&str] = &["get", "set", "unset"];
+
+fn extensions() -> Vec<&'static str> {
+    ConfigFormat::ALL.iter().map(|f| f.extension()).collect()
+}
+
+fn bash(script: &str, config_files_long: Option<&str>, keys: &[String]) -> String {
+    // One pattern for each extension, so the script does not need `extglob`
+    let files = extensions()
+        .iter()
+        .map(|e| format!("$(compgen -f -X '!*.{}' -- \"${{cur}}\")", e))
+        .collect::<Vec<_>>()
+        .join(" ");
+    // The case of each subcommand is named by its path, like `app__config__get)`
+    let key_commands = KEY_COMMANDS
+        .iter()
+        .map(|c| format!("__{}__{})", CONFIG_COMMAND_NAME, c))
+        .collect::<Vec<_>>();
+    let mut lines = Vec::new();
+    let mut in_config_files = false;
+    let mut previous = "";
+    for line in script.lines() {
+        let trimmed = line.trim();
+        let in_key_command = key_commands.iter().any(|c| previous.ends_with(c.as_str()));
+        previous = trimmed;
+        if in_key_command && trimmed.starts_with("opts=") {
+            lines.push(line.replacen("<key>", &keys.join(" "), 1));
+            continue;
+        }
+        if config_files_long.is_some_and(|long| trimmed == format!("--{})", long)) {
+            in_config_files = true;
+        } else if in_config_files && trimmed.starts_with("COMPREPLY=(") {
+            in_config_files = false;
+            let indent = &line[..line.len() - line.trim_start().len()];
+            lines.push(format!(
+                "{}COMPREPLY=({} $(compgen -d -- \"${{cur}}\"))",
+                indent, files
+            ));
+            continue;
+        }
+        lines.push(String::from(line));
+    }
+    lines.join("\n") + "\n"
+}
+
+fn zsh(script: &str, config_files_long: Option<&str>, keys: &[String]) -> String {
+    let option = config_files_long.map(|long| format!("--{}=[", long));
+    let action = format!(":config file:_files -g \"*.({})\"", extensions().join("|"));
+    let key = format!("':key -- {}:_files' \\", KEY_HELP);
+    script
+        .lines()
+        .map(|line| {
+            let is_option = option.as_ref().is_some_and(|o| line.contains(o.as_str()));
+            if is_option && line.ends_with("]' \\") {
+                format!("{}{}' \\", &line[..line.len() - 3], action)
+            } else if line == key && !keys.is_empty() {
+                format!("':key -- {}:({})' \\", KEY_HELP, keys.join(" "))
+            } else {
+                String::from(line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        + "\n"
+}
+
+fn fish(script: &str, bin_name: &str, config_files_long: Option<&str>, keys: &[String]) -> String {
+    let option = config_files_long.map(|long| format!(" -l {}", long));
+    let suffixes = extensions()
+        .iter()
+        .map(|e| format!("__fish_complete_suffix .{}", e))
+        .collect::<Vec<_>>()
+        .join("; ");
+    let mut lines = script
+        .lines()
+        .map(|line| {
+            if option.as_ref().is_some_and(|o| line.contains(o.as_str())) {
+                format!("{} -r -f -a \"({})\"", line, suffixes)
+            } else {
+                String::from(line)
+            }
+        })
+        .collect::<Vec<_>>();
+    if !keys.is_empty() {
+        lines.push(format!(
+            "complete -c {} -n \"__fish_seen_subcommand_from {}\" -f -a \"{}\"",
+            bin_name,
+            KEY_COMMANDS.join(" "),
+            keys.join(" ")
+        ));
+    }
+    lines.join("\n") + "\n"
+}
+
+// Extend clap's completion script for `app`, completing `--config-files` only if its long name is
+// given and the keys of the `config` subcommand only if there are any
+fn script(
+    app: &mut App<'_, '_>,
+    config_files_long: Option<&str>,
+    keys: &[String],
+    bin_name: &str,
+    shell: Shell,
+) -> String {
+    let mut script = Vec::new();
+    app.gen_completions_to(bin_name, shell, &mut script);
+    let script = String::from_utf8_lossy(&script);
+    match shell {
+        Shell::Bash => bash(&script, config_files_long, keys),
+        Shell::Zsh => zsh(&script, config_files_long, keys),
+        Shell::Fish => fish(&script, bin_name, config_files_long, keys),
+        _ => script.into_owned(),
+    }
+}
+
+/// Write a completion script for `app` to `out`
+///
+/// This is clap's completion script extended with what the configopt type `T` knows about config
+/// files. `--config-files` completes files with the extension of a supported config format, and
+/// if the `config` subcommand is enabled its keys complete the dotted keys of `T`. Possible values
+/// are completed by clap itself.
+pub fn generate<T: ConfigOptType>(
+    app: &mut App<'_, '_>,
+    bin_name: &str,
+    shell: Shell,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    // The argument is found by its name, its long name may be changed
+    let config_files_long = T::config_files_arg_name()
+        .and_then(|name| arg_info(app, name))
+        .and_then(|info| info.long);
+    let script = if T::config_command_files().is_some() {
+        let mut app = app.clone().subcommand(ConfigCommand::clap());
+        script(
+            &mut app,
+            config_files_long.as_deref(),
+            &T::config_keys(),
+            bin_name,
+            shell,
+        )
+    } else {
+        script(app, config_files_long.as_deref(), &[], bin_name, shell)
+    };
+    out.write_all(script.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use structopt::clap::Arg;
+
+    fn app() -> App<'static, 'static> {
+        App::new("app")
+            .arg(
+                Arg::with_name("config-files")
+                    .long("config-files")
+                    .help("Paths to config files to read")
+                    .takes_value(true)
+                    .multiple(true),
+            )
+            .arg(
+                Arg::with_name("level")
+                    .long("level")
+                    .takes_value(true)
+                    .possible_values(&["debug", "info"]),
+            )
+            .subcommand(ConfigCommand::clap())
+    }
+
+    fn completions(shell: Shell) -> String {
+        let keys = [String::from("level"), String::from("server.host")];
+        script(&mut app(), Some("config-files"), &keys, "app", shell)
+    }
+
+    #[test]
+    fn bash_completions() {
+        let script = completions(Shell::Bash);
+        assert!(script.contains(
+            "--config-files)\n                    COMPREPLY=($(compgen -f -X '!*.toml' -- \"${cur}\") $(compgen -d -- \"${cur}\"))\n"
+        ));
+        assert!(script.contains("compgen -W \"debug info\""));
+        assert!(script.contains(
+            "app__config__get)\n            opts=\" -h -V  --help --version  level server.host \"\n"
+        ));
+    }
+
+    #[test]
+    fn zsh_completions() {
+        let script = completions(Shell::Zsh);
+        assert!(script.contains(
+            "--config-files=[Paths to config files to read]:config file:_files -g \"*.(toml)\"' \\"
+        ));
+        assert!(script.contains("--level=[]: :(debug info)' \\"));
+        assert!(script.contains("':key -- The dotted key:(level server.host)' \\"));
+    }
+
+    #[test]
+    fn fish_completions() {
+        let script = completions(Shell::Fish);
+        assert!(script.contains(
+            "-l config-files -d 'Paths to config files to read' -r -f -a \"(__fish_complete_suffix .toml)\""
+        ));
+        assert!(script.contains("-l level -r -f -a \"debug info\""));
+        assert!(script.contains(
+            "complete -c app -n \"__fish_seen_subcommand_from get set unset\" -f -a \"level server.host\""
+        ));
+    }
+}
@@ -0,0 +1,412 @@
+use crate::{
+    diff::dotted_path,
+    dotted_key::{remove_document_item, set_document_value, split_key},
+    from_toml_value, parse_config, ConfigFormat, ConfigOptType, Error, Result, STDIN_PATH,
+};
+use serde::de::DeserializeOwned;
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    io::{Error as IoError, ErrorKind as IoErrorKind},
+    path::{Path, PathBuf},
+    process::{self, Command},
+};
+use structopt::clap::{
+    App, AppSettings, Arg, Error as ClapError, ErrorKind as ClapErrorKind, Result as ClapResult,
+    SubCommand,
+};
+use toml::{value::Table, Value};
+use toml_edit::DocumentMut;
+
+/// The name of the `config` subcommand
+pub const CONFIG_COMMAND_NAME: &str = "config";
+
+// The help of the key argument, completions find the argument by it
+pub(crate) const KEY_HELP: &str = "The dotted key";
+
+/// A `config` subcommand reading and changing a config file
+///
+/// Enable it with `#[configopt(config_command)]`. It edits the first writable file of
+/// `--config-files`, or of the default config files if `--config-files` is not set. Only
+/// `--config-files` may come before `config` on the command line, for example
+/// `app --config-files app.toml config set server.port 8080`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigCommand {
+    /// Print the value of a key
+    Get { key: String },
+    /// Set the value of a key, checking it matches the type of the key
+    Set { key: String, value: String },
+    /// Remove a key
+    Unset { key: String },
+    /// Print every key set in the config file
+    List,
+    /// Open the config file in `$VISUAL` or `$EDITOR`, keeping the changes only if they are valid
+    Edit,
+}
+
+impl ConfigCommand {
+    /// The `clap::App` of the subcommand
+    pub fn clap<'a, 'b>() -> App<'a, 'b> {
+        let key = || Arg::with_name("key").help(KEY_HELP).required(true);
+        App::new(CONFIG_COMMAND_NAME)
+            .about("Read and change the config file")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(
+                SubCommand::with_name("get")
+                    .about("Print the value of a key")
+                    .arg(key()),
+            )
+            .subcommand(
+                SubCommand::with_name("set")
+                    .about("Set the value of a key")
+                    .arg(key())
+                    .arg(
+                        Arg::with_name("value")
+                            .help("The value, as TOML or a plain string")
+                            .required(true),
+                    ),
+            )
+            .subcommand(
+                SubCommand::with_name("unset")
+                    .about("Remove a key")
+                    .arg(key()),
+            )
+            .subcommand(
+                SubCommand::with_name("list").about("Print every key set in the config file"),
+            )
+            .subcommand(SubCommand::with_name("edit").about("Open the config file in an editor"))
+    }
+
+    /// Parse the subcommand from any iterator, the first item being `config`
+    pub fn from_iter_safe<I>(iter: I) -> ClapResult<Self>
+    where
+        I: IntoIterator,
+        I::Item: Into<OsString> + Clone,
+    {
+        let matches = Self::clap().get_matches_from_safe(iter)?;
+        let value = |m: &structopt::clap::ArgMatches<'_>, name| {
+            m.value_of(name)
+                .map(String::from)
+                .expect("required argument to be set")
+        };
+        Ok(match matches.subcommand() {
+            ("get", Some(m)) => Self::Get {
+                key: value(m, "key"),
+            },
+            ("set", Some(m)) => Self::Set {
+                key: value(m, "key"),
+                value: value(m, "value"),
+            },
+            ("unset", Some(m)) => Self::Unset {
+                key: value(m, "key"),
+            },
+            ("list", _) => Self::List,
+            ("edit", _) => Self::Edit,
+            _ => unreachable!("a subcommand is required"),
+        })
+    }
+
+    /// Run the subcommand on the first writable file of `paths`, returning its output
+    ///
+    /// Values are read and checked against the configopt type `T` the same as when parsing, so
+    /// upgrades, renamed keys and interpolation apply. Comments and formatting of the file are
+    /// kept.
+    pub fn run<T: ConfigOptType + DeserializeOwned>(&self, paths: &[PathBuf]) -> Result<String> {
+        let path = writable_config_file(paths)?;
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == IoErrorKind::NotFound => String::new(),
+            Err(e) => return Err(Error::ConfigFile(path.to_path_buf(), e)),
+        };
+        match self {
+            Self::Get { key } => Ok(match load::<T>(path, &contents)?.get_value(key)? {
+                Some(Value::String(s)) => s,
+                Some(value) => value.to_string(),
+                None => String::new(),
+            }),
+            Self::Set { key, value } => {
+                let mut document = parse_document(path, &contents)?;
+                let path_parts = key_path(key);
+                // Prefer the value as TOML, but a string key can be set to anything
+                let mut result = Ok(());
+                for value in values(value) {
+                    set_document_value(&mut document, &path_parts, &value);
+                    result = check_set::<T>(path, key, &document.to_string());
+                    if let Ok(()) | Err(Error::UnknownKey(_)) = result {
+                        break;
+                    }
+                }
+                result?;
+                write(path, &document.to_string())?;
+                Ok(String::new())
+            }
+            Self::Unset { key } => {
+                load::<T>(path, &contents)?.get_value(key)?;
+                let mut document = parse_document(path, &contents)?;
+                if remove_document_item(&mut document, &key_path(key)).is_some() {
+                    write(path, &document.to_string())?;
+                }
+                Ok(String::new())
+            }
+            Self::List => {
+                let mut lines = Vec::new();
+                if let Value::Table(table) = read::<T>(path, &contents)? {
+                    list(&table, &[], &mut lines);
+                }
+                Ok(lines.join("\n"))
+            }
+            Self::Edit => {
+                let edited = edit(path, &contents)?;
+                load::<T>(path, &edited)?;
+                write(path, &edited)?;
+                Ok(String::new())
+            }
+        }
+    }
+}
+
+/// Find the `config` subcommand, returning the values of any `--config-files` before it and the
+/// arguments of the subcommand
+pub(crate) fn find(args: &[OsString]) -> Option<(Vec<PathBuf>, &[OsString])> {
+    let mut config_files = Vec::new();
+    let mut in_config_files = false;
+    for (i, arg) in args.iter().enumerate().skip(1) {
+        let arg_str = arg.to_string_lossy();
+        if arg == CONFIG_COMMAND_NAME {
+            return Some((config_files, &args[i..]));
+        } else if arg == "--config-files" {
+            in_config_files = true;
+        } else if let Some(path) = arg_str.strip_prefix("--config-files=") {
+            config_files.push(PathBuf::from(path));
+            in_config_files = false;
+        } else if in_config_files && !arg_str.starts_with('-') {
+            config_files.push(PathBuf::from(arg));
+        } else {
+            return None;
+        }
+    }
+    None
+}
+
+/// The first of `paths` that can be written, either an existing file that is not read only or a
+/// file that does not exist yet in a writable directory
+pub fn writable_config_file(paths: &[PathBuf]) -> Result<&Path> {
+    let writable = |path: &Path| match fs::metadata(path) {
+        Ok(metadata) => metadata.is_file() && !metadata.permissions().readonly(),
+        Err(e) if e.kind() == IoErrorKind::NotFound => path
+            .parent()
+            .map(|p| {
+                if p.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    p
+                }
+            })
+            .and_then(|p| fs::metadata(p).ok())
+            .is_some_and(|m| m.is_dir() && !m.permissions().readonly()),
+        Err(_) => false,
+    };
+    match paths.first() {
+        Some(first) => paths
+            .iter()
+            .map(PathBuf::as_path)
+            .filter(|p| *p != Path::new(STDIN_PATH))
+            .find(|p| writable(p))
+            .ok_or_else(|| {
+                Error::ConfigFile(
+                    first.clone(),
+                    IoError::new(IoErrorKind::PermissionDenied, "no config file is writable"),
+                )
+            }),
+        None => Err(Error::Clap(ClapError::with_description(
+            "no config file to edit, set one with '--config-files'",
+            ClapErrorKind::ArgumentNotFound,
+        ))),
+    }
+}
+
+// Read the contents of the config file at `path` as it is read when parsing
+fn read<T: ConfigOptType>(path: &Path, contents: &str) -> Result<Value> {
+    let mut value = parse_config(ConfigFormat::Toml, contents)
+        .map_err(|e| Error::ConfigFile(path.to_path_buf(), e))?;
+    T::process_config_document(&mut value, path)?;
+    Ok(value)
+}
+
+fn load<T: ConfigOptType + DeserializeOwned>(path: &Path, contents: &str) -> Result<T> {
+    from_toml_value(path, read::<T>(path, contents)?)
+}
+
+fn parse_document(path: &Path, contents: &str) -> Result<DocumentMut> {
+    contents.parse().map_err(|e: toml_edit::TomlError| {
+        Error::ConfigFile(
+            path.to_path_buf(),
+            IoError::new(IoErrorKind::InvalidData, e.to_string()),
+        )
+    })
+}
+
+fn write(path: &Path, contents: &str) -> Result<()> {
+    fs::write(path, contents).map_err(|e| Error::ConfigFile(path.to_path_buf(), e))
+}
+
+fn key_path(key: &str) -> Vec<String> {
+    split_key(key).into_iter().map(String::from).collect()
+}
+
+// The values to try for a value from the command line
+fn values(s: &str) -> Vec<Value> {
+    let string = Value::String(String::from(s));
+    match toml::from_str::<Table>(&format!("v = {}", s))
+        .ok()
+        .and_then(|mut t| t.remove("v"))
+    {
+        Some(value) if !value.is_table() && value != string => vec![value, string],
+        _ => vec![string],
+    }
+}
+
+// Check that the key is read back from the changed document
+fn check_set<T: ConfigOptType + DeserializeOwned>(
+    path: &Path,
+    key: &str,
+    contents: &str,
+) -> Result<()> {
+    let config = load::<T>(path, contents).map_err(|e| match e {
+        Error::ConfigFile(_, e) => Error::InvalidValue(String::from(key), e.to_string()),
+        e => e,
+    })?;
+    match config.get_value(key)? {
+        Some(_) => Ok(()),
+        None => Err(Error::InvalidValue(
+            String::from(key),
+            String::from("the key is not read from config files"),
+        )),
+    }
+}
+
+fn list(table: &Table, prefix: &[String], lines: &mut Vec<String>) {
+    for (name, value) in table {
+        match value {
+            Value::Table(table) => {
+                let mut prefix = prefix.to_vec();
+                prefix.push(name.clone());
+                list(table, &prefix, lines);
+            }
+            value => lines.push(format!("{} = {}", dotted_path(prefix, name), value)),
+        }
+    }
+}
+
+// Edit a copy of the config file so an invalid edit leaves the file unchanged
+fn edit(path: &Path, contents: &str) -> Result<String> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| String::from("vi"));
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| String::from("config.toml"));
+    // The copy ends with the name of the file so editors recognize its format
+    let copy_path = env::temp_dir().join(format!("{}-{}", process::id(), file_name));
+    fs::write(&copy_path, contents).map_err(|e| Error::ConfigFile(copy_path.clone(), e))?;
+    let status = Command::new(program)
+        .args(words)
+        .arg(&copy_path)
+        .status()
+        .map_err(|e| Error::ConfigFile(path.to_path_buf(), e));
+    let edited =
+        fs::read_to_string(&copy_path).map_err(|e| Error::ConfigFile(copy_path.clone(), e));
+    // Failing to remove the copy does not lose the edit
+    let _ = fs::remove_file(&copy_path);
+    let status = status?;
+    if !status.success() {
+        return Err(Error::ConfigFile(
+            path.to_path_buf(),
+            IoError::other(format!("'{}' failed, {}", editor, status)),
+        ));
+    }
+    edited
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn find_config_command() {
+        let a = args(&["app", "config", "get", "port"]);
+        assert_eq!(Some((Vec::new(), &a[1..])), find(&a));
+
+        let a = args(&[
+            "app",
+            "--config-files",
+            "a.toml",
+            "b.toml",
+            "--config-files=c.toml",
+            "config",
+            "list",
+        ]);
+        let files = vec![
+            PathBuf::from("a.toml"),
+            PathBuf::from("b.toml"),
+            PathBuf::from("c.toml"),
+        ];
+        assert_eq!(Some((files, &a[5..])), find(&a));
+
+        assert_eq!(None, find(&args(&["app", "--port", "80", "config"])));
+        assert_eq!(None, find(&args(&["app", "run", "config"])));
+        assert_eq!(None, find(&args(&["app"])));
+    }
+
+    #[test]
+    fn parse_command() {
+        assert_eq!(
+            ConfigCommand::Set {
+                key: String::from("server.port"),
+                value: String::from("8080"),
+            },
+            ConfigCommand::from_iter_safe(&["config", "set", "server.port", "8080"]).unwrap()
+        );
+        assert_eq!(
+            ConfigCommand::List,
+            ConfigCommand::from_iter_safe(&["config", "list"]).unwrap()
+        );
+        assert!(ConfigCommand::from_iter_safe(&["config", "get"]).is_err());
+    }
+
+    #[test]
+    fn command_line_values() {
+        assert_eq!(
+            vec![Value::Integer(80), Value::String(String::from("80"))],
+            values("80")
+        );
+        assert_eq!(vec![Value::String(String::from("a b"))], values("a b"));
+        assert_eq!(
+            vec![
+                Value::String(String::from("a")),
+                Value::String(String::from("\"a\"")),
+            ],
+            values("\"a\"")
+        );
+    }
+
+    #[test]
+    fn list_keys() {
+        let table = toml::toml! {
+            port = 80
+            [server]
+            host = "localhost"
+        };
+        let mut lines = Vec::new();
+        list(table.as_table().unwrap(), &[], &mut lines);
+        assert_eq!(vec!["port = 80", "server.host = \"localhost\""], lines);
+    }
+}
@@ -0,0 +1,30 @@
+use std::str::FromStr;
+
+/// The format of a config document
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConfigFormat {
+    Toml,
+}
+
+impl ConfigFormat {
+    /// Every supported format
+    pub const ALL: &'static [ConfigFormat] = &[Self::Toml];
+
+    /// The file extension of the format
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Toml => "toml",
+        }
+    }
+}
+
+impl FromStr for ConfigFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "toml" => Ok(Self::Toml),
+            _ => Err(format!("unknown config format '{}'", s)),
+        }
+    }
+}
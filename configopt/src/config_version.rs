@@ -0,0 +1,223 @@
+use crate::{
+    dotted_key::{remove_document_item, set_document_value},
+    ConfigOptType, Error, Result,
+};
+use std::{
+    convert::TryFrom,
+    fs,
+    io::{Error as IoError, ErrorKind as IoErrorKind},
+    path::Path,
+};
+use toml::{value::Table, Value};
+use toml_edit::DocumentMut;
+
+/// The top-level key holding the version of a config file's layout
+pub const CONFIG_VERSION_KEY: &str = "config_version";
+
+/// Upgrade the value tree of a config file from one version to the next, from
+/// `#[configopt(upgrade(1 => function))]`
+pub type ConfigUpgrade = fn(&mut Table) -> std::result::Result<(), String>;
+
+/// Upgrade `table` to `version` with `upgrades`, returning the older version it had. A table
+/// without the `config_version` key is taken to be version 1, the layout before versioning.
+///
+/// The `config_version` key is removed from `table`.
+#[doc(hidden)]
+pub fn upgrade_table(
+    table: &mut Table,
+    version: u32,
+    upgrades: &[(u32, ConfigUpgrade)],
+) -> std::result::Result<Option<u32>, String> {
+    let from = match table.remove(CONFIG_VERSION_KEY) {
+        None => 1,
+        Some(Value::Integer(from)) if from >= 1 => u32::try_from(from)
+            .map_err(|e| format!("invalid '{}' {}, err: {}", CONFIG_VERSION_KEY, from, e))?,
+        Some(value) => {
+            return Err(format!(
+                "'{}' must be a positive integer not {}",
+                CONFIG_VERSION_KEY, value
+            ))
+        }
+    };
+    if from > version {
+        return Err(format!(
+            "'{}' {} is newer than the supported version {}",
+            CONFIG_VERSION_KEY, from, version
+        ));
+    }
+    for current in from..version {
+        let (_, upgrade) = upgrades
+            .iter()
+            .find(|(v, _)| *v == current)
+            .ok_or_else(|| {
+                format!(
+                    "there is no upgrade from '{}' {} to {}",
+                    CONFIG_VERSION_KEY,
+                    current,
+                    current + 1
+                )
+            })?;
+        upgrade(table).map_err(|e| {
+            format!(
+                "upgrading from '{}' {} failed, {}",
+                CONFIG_VERSION_KEY, current, e
+            )
+        })?;
+    }
+    Ok(if from < version { Some(from) } else { None })
+}
+
+/// Rewrite the config file at `path` in the current version of `T`, returning the older version
+/// it had
+pub(crate) fn upgrade_config_file<T: ConfigOptType>(path: &Path) -> Result<Option<u32>> {
+    let version = match T::config_version() {
+        Some(version) => version,
+        None => return Ok(None),
+    };
+    let to_error = |e: IoError| Error::ConfigFile(path.to_path_buf(), e);
+    let contents = fs::read_to_string(path).map_err(to_error)?;
+    let mut original = toml::from_str::<Table>(&contents)
+        .map_err(|e| to_error(IoError::new(IoErrorKind::InvalidData, e.to_string())))?;
+    let mut table = original.clone();
+    let from = match T::upgrade_config_table(&mut table)
+        .map_err(|e| Error::UpgradeConfig(path.to_path_buf(), e))?
+    {
+        Some(from) => from,
+        None => return Ok(None),
+    };
+    let mut document = contents
+        .parse::<DocumentMut>()
+        .map_err(|e| to_error(IoError::new(IoErrorKind::InvalidData, e.to_string())))?;
+    let version_path = [String::from(CONFIG_VERSION_KEY)];
+    let had_version = original.remove(CONFIG_VERSION_KEY).is_some();
+    update(&mut document, &[], &original, &table);
+    let upgraded = if had_version {
+        set_document_value(
+            &mut document,
+            &version_path,
+            &Value::Integer(i64::from(version)),
+        );
+        document.to_string()
+    } else {
+        // Keep the version first, separated from any table headers
+        let upgraded = document.to_string();
+        let separator = if upgraded.starts_with('[') { "\n" } else { "" };
+        format!(
+            "{} = {}\n{}{}",
+            CONFIG_VERSION_KEY, version, separator, upgraded
+        )
+    };
+    fs::write(path, upgraded).map_err(to_error)?;
+    Ok(Some(from))
+}
+
+// Write the keys changed from `old` to `new` into the table at `table` of `document`
+fn update(document: &mut DocumentMut, table: &[String], old: &Table, new: &Table) {
+    for key in old.keys().filter(|key| !new.contains_key(*key)) {
+        remove_document_item(document, &[table, std::slice::from_ref(key)].concat());
+    }
+    for (key, new_value) in new {
+        let path = [table, std::slice::from_ref(key)].concat();
+        match (old.get(key), new_value) {
+            (Some(Value::Table(old)), Value::Table(new)) => update(document, &path, old, new),
+            (None, Value::Table(new)) if !new.is_empty() => {
+                update(document, &path, &Table::new(), new)
+            }
+            (Some(old_value), _) if old_value == new_value => {}
+            _ => set_document_value(document, &path, new_value),
+        }
+    }
+}
+
+/// Prefix the generated config with the current version
+pub(crate) fn with_config_version(version: Option<u32>, config: String) -> String {
+    match version {
+        Some(version) => format!("{} = {}\n\n{}", CONFIG_VERSION_KEY, version, config),
+        None => config,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v1_to_v2(table: &mut Table) -> std::result::Result<(), String> {
+        let port = table.remove("listen_port").ok_or("missing 'listen_port'")?;
+        table.insert(String::from("port"), port);
+        Ok(())
+    }
+
+    fn v2_to_v3(table: &mut Table) -> std::result::Result<(), String> {
+        if let Some(Value::Integer(port)) = table.get_mut("port") {
+            *port += 1;
+        }
+        Ok(())
+    }
+
+    const UPGRADES: &[(u32, ConfigUpgrade)] = &[(1, v1_to_v2), (2, v2_to_v3)];
+
+    fn table(s: &str) -> Table {
+        toml::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn upgrade_in_order() {
+        let mut t = table("config_version = 1\nlisten_port = 80");
+        assert_eq!(Ok(Some(1)), upgrade_table(&mut t, 3, UPGRADES));
+        assert_eq!(table("port = 81"), t);
+
+        let mut t = table("config_version = 2\nport = 80");
+        assert_eq!(Ok(Some(2)), upgrade_table(&mut t, 3, UPGRADES));
+        assert_eq!(table("port = 81"), t);
+    }
+
+    #[test]
+    fn current_version_is_unchanged() {
+        let mut t = table("config_version = 3\nport = 80");
+        assert_eq!(Ok(None), upgrade_table(&mut t, 3, UPGRADES));
+        assert_eq!(table("port = 80"), t);
+    }
+
+    #[test]
+    fn missing_version_is_version_1() {
+        let mut t = table("listen_port = 80");
+        assert_eq!(Ok(Some(1)), upgrade_table(&mut t, 3, UPGRADES));
+        assert_eq!(table("port = 81"), t);
+
+        let mut t = table("port = 80");
+        assert_eq!(Ok(None), upgrade_table(&mut t, 1, &[]));
+        assert_eq!(table("port = 80"), t);
+    }
+
+    #[test]
+    fn upgrade_errors() {
+        let mut t = table("config_version = 4");
+        assert_eq!(
+            Err(String::from(
+                "'config_version' 4 is newer than the supported version 3"
+            )),
+            upgrade_table(&mut t, 3, UPGRADES)
+        );
+        let mut t = table("config_version = \"1\"");
+        assert_eq!(
+            Err(String::from(
+                "'config_version' must be a positive integer not \"1\""
+            )),
+            upgrade_table(&mut t, 3, UPGRADES)
+        );
+        let mut t = table("config_version = 1");
+        assert_eq!(
+            Err(String::from(
+                "upgrading from 'config_version' 1 failed, missing 'listen_port'"
+            )),
+            upgrade_table(&mut t, 3, UPGRADES)
+        );
+        let mut t = table("config_version = 1\nlisten_port = 80");
+        assert_eq!(
+            Err(String::from(
+                "there is no upgrade from 'config_version' 2 to 3"
+            )),
+            upgrade_table(&mut t, 3, &UPGRADES[..1])
+        );
+    }
+}
@@ -0,0 +1,245 @@
+//! Relationships between arguments like `required`, `requires` and `conflicts_with`
+//!
+//! These are trimmed from the `configopt` type so that config files can fill in values. They are
+//! recorded by the derive and checked once every layer is merged, so values from config files are
+//! covered too.
+
+use crate::{
+    validate::{layer, ConfigLayer},
+    ConfigOptType, Error, Result,
+};
+use std::path::PathBuf;
+use toml::Value;
+
+#[doc(hidden)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Constraint {
+    Required,
+    Requires(Vec<&'static str>),
+    ConflictsWith(Vec<&'static str>),
+    RequiredUnlessAny(Vec<&'static str>),
+    RequiredUnlessAll(Vec<&'static str>),
+    /// Pairs of an argument name and a value
+    RequiredIfAny(Vec<(&'static str, &'static str)>),
+    /// Pairs of a value of this argument and the argument it requires
+    RequiresIf(Vec<(&'static str, &'static str)>),
+}
+
+/// An argument and its constraints
+#[doc(hidden)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConstraintArg {
+    /// The name of the argument on the command line
+    pub name: &'static str,
+    /// How clap shows the argument, like `--port <port>`
+    pub usage: &'static str,
+    /// The dotted config key of the argument
+    pub key: String,
+    pub constraints: Vec<Constraint>,
+}
+
+// The values of a key as strings, an array has a value for each element
+pub(crate) fn values(configopt: &impl ConfigOptType, key: &str) -> Vec<String> {
+    let to_string = |value: &Value| match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    };
+    match configopt.get_value(key) {
+        Ok(Some(Value::Array(values))) => values.iter().map(to_string).collect(),
+        Ok(Some(value)) => vec![to_string(&value)],
+        _ => Vec::new(),
+    }
+}
+
+fn quoted(names: &[&str]) -> String {
+    names
+        .iter()
+        .map(|name| format!("'{}'", name))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// The arguments sharing one namespace on the command line
+struct Group<'a, T> {
+    configopt: &'a T,
+    args: &'a [ConstraintArg],
+    from_cli: Option<&'a T>,
+    files: &'a [(PathBuf, T)],
+}
+
+impl<'a, T: ConfigOptType> Group<'a, T> {
+    fn find(&self, name: &str) -> Option<&'a ConstraintArg> {
+        self.args.iter().find(|arg| arg.name == name)
+    }
+
+    fn layer(&self, arg: &ConstraintArg) -> ConfigLayer {
+        layer(&arg.key, self.from_cli, self.files)
+    }
+
+    // Default values do not count as being present, like with clap
+    fn is_present(&self, arg: &ConstraintArg) -> bool {
+        self.layer(arg) != ConfigLayer::Default
+    }
+
+    fn is_name_present(&self, name: &str) -> bool {
+        self.find(name).is_some_and(|arg| self.is_present(arg))
+    }
+
+    fn has_value(&self, name: &str, value: &str) -> bool {
+        self.find(name).is_some_and(|arg| {
+            values(self.configopt, &arg.key).iter().any(|v| v == value)
+        })
+    }
+
+    // Name the argument the way clap does, along with the file that set it
+    fn describe(&self, arg: &ConstraintArg) -> String {
+        match self.layer(arg) {
+            ConfigLayer::File(path) => format!(
+                "'{}' (config key '{}' from file '{}')",
+                arg.usage,
+                arg.key,
+                path.display()
+            ),
+            _ => format!("'{}'", arg.usage),
+        }
+    }
+
+    fn describe_name(&self, name: &str) -> String {
+        self.find(name)
+            .map_or_else(|| format!("'{}'", name), |arg| self.describe(arg))
+    }
+
+    fn check(&self, problems: &mut Vec<String>) {
+        for arg in self.args {
+            let present = self.is_present(arg);
+            let missing = || format!("Missing required value for '{}'", arg.usage);
+            for constraint in &arg.constraints {
+                match constraint {
+                    Constraint::Required if !present => problems.push(missing()),
+                    Constraint::RequiredUnlessAny(names)
+                        if !present && !names.iter().any(|n| self.is_name_present(n)) =>
+                    {
+                        problems.push(format!(
+                            "{} which is required unless one of {} is provided",
+                            missing(),
+                            quoted(names)
+                        ))
+                    }
+                    Constraint::RequiredUnlessAll(names)
+                        if !present && !names.iter().all(|n| self.is_name_present(n)) =>
+                    {
+                        problems.push(format!(
+                            "{} which is required unless all of {} are provided",
+                            missing(),
+                            quoted(names)
+                        ))
+                    }
+                    Constraint::RequiredIfAny(pairs) if !present => {
+                        if let Some((name, value)) =
+                            pairs.iter().find(|(n, v)| self.has_value(n, v))
+                        {
+                            problems.push(format!(
+                                "{} which is required when {} is '{}'",
+                                missing(),
+                                self.describe_name(name),
+                                value
+                            ))
+                        }
+                    }
+                    Constraint::Requires(names) if present => {
+                        for name in names.iter().filter(|n| !self.is_name_present(n)) {
+                            problems.push(format!(
+                                "Missing required value for '{}' which is required by {}",
+                                self.find(name).map_or(*name, |a| a.usage),
+                                self.describe(arg)
+                            ))
+                        }
+                    }
+                    Constraint::RequiresIf(pairs) if present => {
+                        for (value, name) in pairs.iter().filter(|(v, n)| {
+                            self.has_value(arg.name, v) && !self.is_name_present(n)
+                        }) {
+                            problems.push(format!(
+                                "Missing required value for '{}' which is required when {} is '{}'",
+                                self.find(name).map_or(*name, |a| a.usage),
+                                self.describe(arg),
+                                value
+                            ))
+                        }
+                    }
+                    Constraint::ConflictsWith(names) if present => {
+                        for name in names.iter().filter(|n| self.is_name_present(n)) {
+                            problems.push(format!(
+                                "The argument {} cannot be used with {}",
+                                self.describe(arg),
+                                self.describe_name(name)
+                            ))
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Check the constraints of the merged `configopt`. `from_cli` and `files` are the layers it was
+/// merged from, they tell whether a value was set on the command line or in a config file.
+pub(crate) fn problems<T: ConfigOptType>(
+    configopt: &T,
+    from_cli: Option<&T>,
+    files: &[(PathBuf, T)],
+) -> Vec<String> {
+    let mut group = Vec::new();
+    let mut groups = Vec::new();
+    configopt.constraint_args_with_prefix(&[], &mut group, &mut groups);
+    groups.insert(0, group);
+    // clap checks `required` for the actual app, any other constraint needs the layers
+    if groups
+        .iter()
+        .flatten()
+        .flat_map(|arg| &arg.constraints)
+        .all(|c| *c == Constraint::Required)
+    {
+        return Vec::new();
+    }
+    let mut problems = Vec::new();
+    for args in &groups {
+        let group = Group {
+            configopt,
+            args,
+            from_cli,
+            files,
+        };
+        group.check(&mut problems);
+    }
+    problems
+}
+
+/// The required arguments without a value in the merged `configopt`, named the way clap does
+pub(crate) fn missing<T: ConfigOptType>(configopt: &T) -> Vec<&'static str> {
+    let mut group = Vec::new();
+    let mut groups = Vec::new();
+    configopt.constraint_args_with_prefix(&[], &mut group, &mut groups);
+    groups.insert(0, group);
+    groups
+        .iter()
+        .flatten()
+        .filter(|arg| arg.constraints.contains(&Constraint::Required))
+        .filter(|arg| matches!(configopt.get_value(&arg.key), Ok(None)))
+        .map(|arg| arg.usage)
+        .collect()
+}
+
+pub(crate) fn check<T: ConfigOptType>(
+    configopt: &T,
+    from_cli: &T,
+    files: &[(PathBuf, T)],
+) -> Result<()> {
+    let problems = problems(configopt, Some(from_cli), files);
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Constraints(problems))
+    }
+}
@@ -0,0 +1,80 @@
+use serde::Serialize;
+use std::fmt;
+use toml::Value;
+
+/// A config key that differs between two configurations
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ConfigChange {
+    /// The dotted path of the key
+    pub path: String,
+    /// The old value, `None` if it was unset
+    pub old: Option<Value>,
+    /// The new value, `None` if it is unset
+    pub new: Option<Value>,
+}
+
+impl fmt::Display for ConfigChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn value(v: &Option<Value>) -> String {
+            v.as_ref()
+                .map_or_else(|| String::from("<unset>"), ToString::to_string)
+        }
+        write!(f, "{}: {} -> {}", self.path, value(&self.old), value(&self.new))
+    }
+}
+
+/// The list of changes between two configurations
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct ConfigDiff(pub Vec<ConfigChange>);
+
+impl ConfigDiff {
+    /// Check if there are no changes
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Get the changes
+    pub fn changes(&self) -> &[ConfigChange] {
+        &self.0
+    }
+}
+
+impl fmt::Display for ConfigDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.0 {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/// Join a key prefix and name into a dotted path
+#[doc(hidden)]
+pub fn dotted_path(prefix: &[String], name: &str) -> String {
+    if prefix.is_empty() {
+        String::from(name)
+    } else {
+        format!("{}.{}", prefix.join("."), name)
+    }
+}
+
+/// Record a change if the serialized `old` and `new` values differ
+#[doc(hidden)]
+pub fn diff_value<T: Serialize + ?Sized>(
+    prefix: &[String],
+    name: &str,
+    old: &T,
+    new: &T,
+    changes: &mut Vec<ConfigChange>,
+) {
+    let old = Value::try_from(old).ok();
+    let new = Value::try_from(new).ok();
+    if old != new {
+        changes.push(ConfigChange {
+            path: dotted_path(prefix, name),
+            old,
+            new,
+        });
+    }
+}
@@ -0,0 +1,208 @@
+use serde::{de::DeserializeOwned, Serialize};
+use toml::{value::Table, Value};
+use toml_edit::{DocumentMut, Item, TableLike};
+
+/// Serialize a field, `None` if it is not set
+#[doc(hidden)]
+pub fn to_toml_value<T: Serialize + ?Sized>(field: &T) -> Option<Value> {
+    Value::try_from(field).ok()
+}
+
+/// Deserialize `value` into a field, failing if it does not match the type of the field
+#[doc(hidden)]
+pub fn from_toml_value_into<T: DeserializeOwned>(
+    field: &mut T,
+    value: Value,
+) -> std::result::Result<(), String> {
+    *field = value
+        .try_into()
+        .map_err(|e: toml::de::Error| e.to_string())?;
+    Ok(())
+}
+
+// Split a dotted key into its path
+pub(crate) fn split_key(key: &str) -> Vec<&str> {
+    key.split('.').collect()
+}
+
+// Interpret a string as a TOML value, falling back to a string. This lets environment variables
+// and command line values set numbers and bools.
+pub(crate) fn scalar(s: &str) -> Value {
+    toml::from_str::<Table>(&format!("v = {}", s))
+        .ok()
+        .and_then(|mut t| t.remove("v"))
+        .filter(|v| !v.is_table() && !v.is_array())
+        .unwrap_or_else(|| Value::String(String::from(s)))
+}
+
+// Convert a value to be written into a document, writing tables inline
+fn document_value(value: &Value) -> toml_edit::Value {
+    match value {
+        Value::String(s) => s.as_str().into(),
+        Value::Integer(i) => (*i).into(),
+        Value::Float(f) => (*f).into(),
+        Value::Boolean(b) => (*b).into(),
+        Value::Datetime(d) => d
+            .to_string()
+            .parse::<toml_edit::Datetime>()
+            .expect("a TOML datetime to be valid")
+            .into(),
+        Value::Array(values) => values
+            .iter()
+            .map(document_value)
+            .collect::<toml_edit::Array>()
+            .into(),
+        Value::Table(table) => table
+            .iter()
+            .map(|(k, v)| (k.as_str(), document_value(v)))
+            .collect::<toml_edit::InlineTable>()
+            .into(),
+    }
+}
+
+// The table at `path` in `document`, creating missing tables and replacing anything in the way
+// if `create` is set
+pub(crate) fn document_table_mut<'a>(
+    document: &'a mut DocumentMut,
+    path: &[String],
+    create: bool,
+) -> Option<&'a mut dyn TableLike> {
+    let mut table: &mut dyn TableLike = document.as_table_mut();
+    for key in path {
+        if create && !table.get(key).is_some_and(Item::is_table_like) {
+            let mut new_table = toml_edit::Table::new();
+            new_table.set_implicit(true);
+            table.insert(key, Item::Table(new_table));
+        }
+        table = table.get_mut(key).and_then(Item::as_table_like_mut)?;
+    }
+    Some(table)
+}
+
+// Set the value at `path` in `document`, keeping the comments around an existing value
+pub(crate) fn set_document_value(document: &mut DocumentMut, path: &[String], value: &Value) {
+    let (key, parents) = match path.split_last() {
+        Some(split) => split,
+        None => return,
+    };
+    let table = document_table_mut(document, parents, true).expect("the table to be created");
+    let mut value = document_value(value);
+    match table.get_mut(key) {
+        Some(Item::Value(old)) => {
+            *value.decor_mut() = old.decor().clone();
+            *old = value;
+        }
+        _ => {
+            table.insert(key, Item::Value(value));
+        }
+    }
+}
+
+// Remove the key at `path` from `document` with its comments, returning it if it was set. Keys
+// inside arrays of tables are not found.
+pub(crate) fn remove_document_item(
+    document: &mut DocumentMut,
+    path: &[String],
+) -> Option<(toml_edit::Key, Item)> {
+    let (key, parents) = path.split_last()?;
+    let table = document_table_mut(document, parents, false)?;
+    let formatted = table.key(key)?.clone();
+    table.remove(key).map(|item| (formatted, item))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_values() {
+        assert_eq!(Some(Value::Integer(80)), to_toml_value(&Some(80)));
+        assert_eq!(None, to_toml_value(&None::<u16>));
+
+        let mut field = None::<u16>;
+        from_toml_value_into(&mut field, Value::Integer(8080)).unwrap();
+        assert_eq!(Some(8080), field);
+        assert!(from_toml_value_into(&mut field, Value::String(String::from("NaN"))).is_err());
+        assert_eq!(Some(8080), field);
+    }
+
+    const DOCUMENT: &str = r#"# The port
+port = 80 # the default
+
+[server]
+host = "localhost" # a comment with a # in it
+ports = [
+    1,
+    2,
+]
+
+[[rules]]
+name = "first"
+"#;
+
+    fn path(key: &str) -> Vec<String> {
+        split_key(key).into_iter().map(String::from).collect()
+    }
+
+    #[test]
+    fn set_in_document() {
+        let mut document = DOCUMENT.parse::<DocumentMut>().unwrap();
+        set_document_value(&mut document, &path("port"), &Value::Integer(8080));
+        set_document_value(
+            &mut document,
+            &path("server.ports"),
+            &toml::toml!(v = [3])["v"],
+        );
+        set_document_value(&mut document, &path("server.timeout"), &Value::Integer(5));
+        set_document_value(&mut document, &path("client.retries"), &Value::Integer(3));
+        assert_eq!(
+            document.to_string(),
+            r#"# The port
+port = 8080 # the default
+
+[server]
+host = "localhost" # a comment with a # in it
+ports = [3]
+timeout = 5
+
+[[rules]]
+name = "first"
+
+[client]
+retries = 3
+"#
+        );
+
+        let mut document = "server = { host = \"x\" } # inline\n"
+            .parse::<DocumentMut>()
+            .unwrap();
+        set_document_value(&mut document, &path("server.tls.cert"), &Value::Integer(1));
+        let contents = document.to_string();
+        assert!(contents.ends_with("} # inline\n"));
+        assert_eq!(
+            toml::toml! {
+                server = { host = "x", tls = { cert = 1 } }
+            },
+            toml::from_str::<Value>(&contents).unwrap()
+        );
+    }
+
+    #[test]
+    fn remove_from_document() {
+        let mut document = DOCUMENT.parse::<DocumentMut>().unwrap();
+        assert!(remove_document_item(&mut document, &path("server.ports")).is_some());
+        assert!(remove_document_item(&mut document, &path("port")).is_some());
+        assert!(remove_document_item(&mut document, &path("missing")).is_none());
+        assert!(remove_document_item(&mut document, &path("rules.name")).is_none());
+        assert_eq!(
+            document.to_string(),
+            r#"
+[server]
+host = "localhost" # a comment with a # in it
+
+[[rules]]
+name = "first"
+"#
+        );
+    }
+}
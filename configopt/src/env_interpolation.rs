@@ -0,0 +1,129 @@
+use crate::{Error, Result};
+use std::{env, path::Path};
+use toml::Value;
+
+/// Expand environment variables in every string of `value`.
+///
+/// `${VAR}` is replaced with the value of `VAR`. `${VAR:-default}` uses `default` if `VAR` is unset
+/// or empty. `$${` is an escape for a literal `${`. Any other `$` is left untouched.
+pub fn interpolate_env(value: &mut Value, path: &Path, key: &str) -> Result<()> {
+    match value {
+        Value::String(s) => {
+            *s = expand(s, |var| env::var(var).ok())
+                .map_err(|e| Error::Interpolation(path.to_path_buf(), String::from(key), e))?;
+        }
+        Value::Array(values) => {
+            for (i, value) in values.iter_mut().enumerate() {
+                interpolate_env(value, path, &format!("{}[{}]", key, i))?;
+            }
+        }
+        Value::Table(table) => {
+            for (k, value) in table.iter_mut() {
+                interpolate_env(value, path, &format!("{}.{}", key, k))?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn expand(s: &str, lookup: impl Fn(&str) -> Option<String>) -> std::result::Result<String, String> {
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('$') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if rest.starts_with("$${") {
+            result.push_str("${");
+            rest = &rest[3..];
+        } else if rest.starts_with("${") {
+            let end = rest
+                .find('}')
+                .ok_or_else(|| format!("unterminated variable in '{}'", s))?;
+            let expr = &rest[2..end];
+            let (var, default) = match expr.find(":-") {
+                Some(i) => (&expr[..i], Some(&expr[i + 2..])),
+                None => (expr, None),
+            };
+            if var.is_empty() {
+                return Err(format!("empty variable name in '{}'", s));
+            }
+            let value = match default {
+                Some(default) => lookup(var)
+                    .filter(|v| !v.is_empty())
+                    .unwrap_or_else(|| String::from(default)),
+                None => lookup(var)
+                    .ok_or_else(|| format!("unknown environment variable '{}'", var))?,
+            };
+            result.push_str(&value);
+            rest = &rest[end + 1..];
+        } else {
+            result.push('$');
+            rest = &rest[1..];
+        }
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(var: &str) -> Option<String> {
+        match var {
+            "DATA_DIR" => Some(String::from("/var/lib/app")),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn expand_variables() {
+        assert_eq!(expand("no vars", lookup).unwrap(), "no vars");
+        assert_eq!(
+            expand("${DATA_DIR}/cache", lookup).unwrap(),
+            "/var/lib/app/cache"
+        );
+        assert_eq!(
+            expand("${DATA_DIR}:${DATA_DIR}", lookup).unwrap(),
+            "/var/lib/app:/var/lib/app"
+        );
+        assert_eq!(expand("${MISSING:-/tmp}", lookup).unwrap(), "/tmp");
+        assert_eq!(expand("${EMPTY:-/tmp}", lookup).unwrap(), "/tmp");
+        assert_eq!(expand("${EMPTY}", lookup).unwrap(), "");
+        assert_eq!(expand("${MISSING:-}", lookup).unwrap(), "");
+        assert_eq!(expand("cost $5", lookup).unwrap(), "cost $5");
+    }
+
+    #[test]
+    fn expand_escape() {
+        assert_eq!(expand("$${DATA_DIR}", lookup).unwrap(), "${DATA_DIR}");
+        assert_eq!(
+            expand("$${DATA_DIR}/${DATA_DIR}", lookup).unwrap(),
+            "${DATA_DIR}//var/lib/app"
+        );
+    }
+
+    #[test]
+    fn expand_errors() {
+        assert!(expand("${MISSING}", lookup).is_err());
+        assert!(expand("${DATA_DIR", lookup).is_err());
+        assert!(expand("${}", lookup).is_err());
+    }
+
+    #[test]
+    fn interpolate_env_error_names_key() {
+        let mut value = Value::Array(vec![Value::String(String::from(
+            "${CONFIGOPT_SURELY_NOT_SET}",
+        ))]);
+        let err = interpolate_env(&mut value, Path::new("/etc/app.toml"), "paths").unwrap_err();
+        match err {
+            Error::Interpolation(path, key, _) => {
+                assert_eq!(path, Path::new("/etc/app.toml"));
+                assert_eq!(key, "paths[0]");
+            }
+            _ => panic!("unexpected error {}", err),
+        }
+    }
+}
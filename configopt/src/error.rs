@@ -1,3 +1,4 @@
+use crate::validate::ValidationError;
 use std::{
     fmt,
     io::{self, Error as IoError, ErrorKind as IoErrorKind, Write},
@@ -9,11 +10,88 @@ use structopt::clap::{Error as ClapError, ErrorKind as ClapErrorKind};
 #[derive(Debug)]
 pub enum Error {
     ConfigGenerated(String),
+    ConfigCommand(String),
+    ConfigCheck(Vec<String>),
+    ConfigExplained(String),
     ConfigFile(PathBuf, IoError),
+    ConfigStr(IoError),
+    Interpolation(PathBuf, String, String),
+    UpgradeConfig(PathBuf, String),
+    UnknownKey(String),
+    InvalidValue(String, String),
+    Validation(Vec<ValidationError>),
+    Constraints(Vec<String>),
     ExpectedError(ClapError),
     Clap(ClapError),
 }
 
+/// The kind of an [`Error`](enum.Error.html), used to choose its exit code
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// The command line was invalid
+    Usage,
+    /// A config file does not exist
+    ConfigFileMissing,
+    /// A config file, string, or key was invalid
+    Config,
+    /// A config file could not be read due to its permissions
+    PermissionDenied,
+    /// An error reading or writing other than reading a config file
+    Io,
+    /// An internal error
+    Software,
+    /// Not an actual failure, the help, version, a generated config, the output of the `config`
+    /// subcommand, checking a valid config, or explaining the config was requested
+    Output,
+}
+
+/// The exit code used for each [`ErrorKind`](enum.ErrorKind.html)
+///
+/// The default follows the conventions of `sysexits.h`. Individual codes can be overridden, for
+/// example `ExitCodes { usage: 2, ..ExitCodes::default() }`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExitCodes {
+    pub usage: i32,
+    pub config_file_missing: i32,
+    pub config: i32,
+    pub permission_denied: i32,
+    pub io: i32,
+    pub software: i32,
+    pub output: i32,
+}
+
+impl ExitCodes {
+    /// Exit codes from `sysexits.h`
+    pub const SYSEXITS: Self = Self {
+        usage: 64,               // EX_USAGE
+        config_file_missing: 66, // EX_NOINPUT
+        config: 78,              // EX_CONFIG
+        permission_denied: 77,   // EX_NOPERM
+        io: 74,                  // EX_IOERR
+        software: 70,            // EX_SOFTWARE
+        output: 0,
+    };
+
+    /// The exit code for errors of `kind`
+    pub fn code(&self, kind: ErrorKind) -> i32 {
+        match kind {
+            ErrorKind::Usage => self.usage,
+            ErrorKind::ConfigFileMissing => self.config_file_missing,
+            ErrorKind::Config => self.config,
+            ErrorKind::PermissionDenied => self.permission_denied,
+            ErrorKind::Io => self.io,
+            ErrorKind::Software => self.software,
+            ErrorKind::Output => self.output,
+        }
+    }
+}
+
+impl Default for ExitCodes {
+    fn default() -> Self {
+        Self::SYSEXITS
+    }
+}
+
 macro_rules! wlnerr(
     ($($arg:tt)*) => ({
         use std::io::{Write, stderr};
@@ -26,17 +104,64 @@ impl Error {
     pub fn use_stderr(&self) -> bool {
         match self {
             Self::ConfigGenerated(_) => false,
+            Self::ConfigCommand(_) => false,
+            Self::ConfigCheck(problems) => !problems.is_empty(),
+            Self::ConfigExplained(_) => false,
             Self::ConfigFile(_, _) => true,
+            Self::ConfigStr(_) => true,
+            Self::Interpolation(_, _, _) => true,
+            Self::UpgradeConfig(_, _) => true,
+            Self::UnknownKey(_) => true,
+            Self::InvalidValue(_, _) => true,
+            Self::Validation(_) => true,
+            Self::Constraints(_) => true,
             Self::ExpectedError(e) => e.use_stderr(),
             Self::Clap(e) => e.use_stderr(),
         }
     }
 
+    /// The kind of this error
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::ConfigGenerated(_) => ErrorKind::Output,
+            Self::ConfigCommand(_) => ErrorKind::Output,
+            Self::ConfigCheck(problems) if problems.is_empty() => ErrorKind::Output,
+            Self::ConfigCheck(_) => ErrorKind::Config,
+            Self::ConfigExplained(_) => ErrorKind::Output,
+            Self::ConfigFile(_, e) => match e.kind() {
+                IoErrorKind::NotFound => ErrorKind::ConfigFileMissing,
+                IoErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+                _ => ErrorKind::Config,
+            },
+            Self::ConfigStr(_) => ErrorKind::Config,
+            Self::Interpolation(_, _, _) => ErrorKind::Config,
+            Self::UpgradeConfig(_, _) => ErrorKind::Config,
+            Self::UnknownKey(_) => ErrorKind::Config,
+            Self::InvalidValue(_, _) => ErrorKind::Config,
+            Self::Validation(_) => ErrorKind::Config,
+            Self::Constraints(_) => ErrorKind::Config,
+            Self::ExpectedError(_) => ErrorKind::Software,
+            Self::Clap(e) => match e.kind {
+                ClapErrorKind::HelpDisplayed | ClapErrorKind::VersionDisplayed => ErrorKind::Output,
+                ClapErrorKind::Io | ClapErrorKind::Format => ErrorKind::Io,
+                _ => ErrorKind::Usage,
+            },
+        }
+    }
+
     /// Prints the error to `stderr` or `stdout` and exits.
     ///
-    /// This exits with a `0` when writing to `stdout` and `1` when writing to `stderr`.
+    /// This exits with the [`sysexits`](struct.ExitCodes.html#associatedconstant.SYSEXITS) code
+    /// for the kind of error, which is `0` for the help, version, or a generated config.
     pub fn exit(&self) -> ! {
-        self.exit_with_codes(1, 0)
+        self.exit_with_policy(&ExitCodes::default())
+    }
+
+    /// Prints the error to `stderr` or `stdout` and exits with the code `codes` has for the kind
+    /// of error.
+    pub fn exit_with_policy(&self, codes: &ExitCodes) -> ! {
+        let code = codes.code(self.kind());
+        self.exit_with_codes(code, code)
     }
 
     /// Prints the error to `stderr` or `stdout` and exits with the specified code.
@@ -63,7 +188,27 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Error::ConfigGenerated(config) => write!(f, "{}", config),
+            Self::ConfigCommand(output) => write!(f, "{}", output),
+            Self::ConfigCheck(problems) if problems.is_empty() => write!(f, "The config is valid"),
+            Self::ConfigCheck(problems) => {
+                write!(f, "The config is invalid:")?;
+                for problem in problems {
+                    write!(f, "\n  {}", problem)?;
+                }
+                Ok(())
+            }
+            Self::ConfigExplained(explanation) => write!(f, "{}", explanation),
             Self::ConfigFile(path, e) => write!(f, "Failed to parse file '{}', err: {}", path.to_string_lossy(), e),
+            Self::ConfigStr(e) => write!(f, "Failed to parse config, err: {}", e),
+            Self::Interpolation(path, key, e) => write!(f, "Failed to interpolate key '{}' in file '{}', err: {}", key, path.to_string_lossy(), e),
+            Self::UpgradeConfig(path, e) => write!(f, "Failed to upgrade file '{}', err: {}", path.to_string_lossy(), e),
+            Self::UnknownKey(key) => write!(f, "Unknown config key '{}'", key),
+            Self::InvalidValue(key, e) => write!(f, "Invalid value for config key '{}', err: {}", key, e),
+            Self::Validation(errors) => {
+                let errors = errors.iter().map(ToString::to_string).collect::<Vec<_>>();
+                write!(f, "{}", errors.join("\n"))
+            }
+            Self::Constraints(problems) => write!(f, "{}", problems.join("\n")),
             Error::ExpectedError(e) => write!(f, "The `configopt` app generated an error, but the actual app did not. This should never happen. err: {}", e),
             Error::Clap(e) => write!(f, "{}", e),
         }
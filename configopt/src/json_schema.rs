@@ -0,0 +1,102 @@
+//! Helpers for building the JSON Schema of a config file
+use crate::arg_info::ArgInfo;
+use structopt::clap::App;
+
+pub use serde_json::{Map, Value};
+
+const SCHEMA: &str = "http://json-schema.org/draft-07/schema#";
+
+fn default_value(ty: Option<&str>, default: &str) -> Value {
+    let value = match ty {
+        Some("integer") => default.parse::<i64>().ok().map(Value::from),
+        Some("number") => default.parse::<f64>().ok().map(Value::from),
+        Some("boolean") => default.parse::<bool>().ok().map(Value::from),
+        _ => None,
+    };
+    value.unwrap_or_else(|| Value::from(default))
+}
+
+/// The schema of a single config key
+///
+/// `ty` is the JSON type of a single value. If `array` is set the key is an array of these values.
+#[doc(hidden)]
+pub fn property(ty: Option<&str>, array: bool, info: &ArgInfo) -> Value {
+    let mut item = Map::new();
+    if let Some(ty) = ty {
+        item.insert(String::from("type"), Value::from(ty));
+    }
+    if !info.possible_values.is_empty() {
+        item.insert(
+            String::from("enum"),
+            info.possible_values
+                .iter()
+                .map(|v| default_value(ty, v))
+                .collect(),
+        );
+    }
+    let mut property = if array {
+        let mut property = Map::new();
+        property.insert(String::from("type"), Value::from("array"));
+        property.insert(String::from("items"), Value::Object(item));
+        property
+    } else {
+        item
+    };
+    if !info.help.is_empty() {
+        property.insert(String::from("description"), Value::from(info.help.as_str()));
+    }
+    if let Some(default) = &info.default_value {
+        let default = default_value(ty, default);
+        let default = if array {
+            Value::Array(vec![default])
+        } else {
+            default
+        };
+        property.insert(String::from("default"), default);
+    }
+    Value::Object(property)
+}
+
+/// The schema of a table of config keys
+///
+/// No key is `required`, any of them can be given on the command line or in another config file.
+#[doc(hidden)]
+pub fn object(properties: Map<String, Value>, definitions: Map<String, Value>) -> Value {
+    let mut object = Map::new();
+    object.insert(String::from("type"), Value::from("object"));
+    object.insert(String::from("properties"), Value::Object(properties));
+    if !definitions.is_empty() {
+        object.insert(String::from("definitions"), Value::Object(definitions));
+    }
+    Value::Object(object)
+}
+
+/// A reference to the schema `name` in the definitions of the document
+#[doc(hidden)]
+pub fn reference(name: &str) -> Value {
+    let mut reference = Map::new();
+    reference.insert(
+        String::from("$ref"),
+        Value::from(format!("#/definitions/{}", name)),
+    );
+    Value::Object(reference)
+}
+
+/// The schema of a full config document for `app`
+#[doc(hidden)]
+pub fn document(
+    app: &App<'_, '_>,
+    properties: Map<String, Value>,
+    definitions: Map<String, Value>,
+) -> Value {
+    let mut document = match object(properties, definitions) {
+        Value::Object(object) => object,
+        _ => unreachable!(),
+    };
+    document.insert(String::from("$schema"), Value::from(SCHEMA));
+    document.insert(String::from("title"), Value::from(app.p.meta.name.as_str()));
+    if let Some(about) = app.p.meta.long_about.or(app.p.meta.about) {
+        document.insert(String::from("description"), Value::from(about));
+    }
+    Value::Object(document)
+}
@@ -1,78 +1,171 @@
-mod arena_trait;
+mod arg_info;
+mod builder;
+mod check_config;
+mod command_line;
+pub mod completions;
+mod config_command;
+mod config_format;
+mod config_version;
 mod configopt_arg_to_os_string;
 mod configopt_bool;
+#[doc(hidden)]
+pub mod constraints;
+mod diff;
+mod dotted_key;
+mod env_interpolation;
 mod error;
+pub mod json_schema;
+mod man_page;
+mod migrate;
+mod parse_outcome;
+pub mod reference;
+mod relative_path;
+pub mod validate;
+#[cfg(feature = "watch")]
+pub mod watch;
 
-use arena_trait::Arena;
-use colosseum::{sync::Arena as SyncArena, unsync::Arena as UnsyncArena};
+use completions::Shell;
+use colosseum::unsync::Arena as UnsyncArena;
 use lazy_static::lazy_static;
 use serde::de::DeserializeOwned;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::{
     env,
     ffi::OsString,
     fs,
-    io::{self, Write},
+    io::{self, Read, Write},
     process,
+    sync::Mutex,
 };
 use structopt::{
-    clap::{App, ErrorKind as ClapErrorKind, Result as ClapResult},
+    clap::{Arg, ArgMatches, Error as ClapError, ErrorKind as ClapErrorKind, Result as ClapResult},
     StructOpt,
 };
 
+#[doc(hidden)]
+pub use arg_info::{arg_info, subcommand_app, ArgInfo};
+pub use builder::{ConfigOptBuilder, ConfigOptMatches, ConfigSource};
+#[doc(hidden)]
+pub use check_config::check_config_file;
+pub use config_command::{writable_config_file, ConfigCommand, CONFIG_COMMAND_NAME};
+pub use config_format::ConfigFormat;
+pub use config_version::{ConfigUpgrade, CONFIG_VERSION_KEY};
+#[doc(hidden)]
+pub use config_version::upgrade_table;
 pub use configopt_arg_to_os_string::ConfigOptArgToOsString;
 pub use configopt_bool::ConfigOptBool;
 pub use configopt_derive::{configopt_fields, ConfigOpt};
-pub use error::{Error, Result};
+pub use diff::{diff_value, dotted_path, ConfigChange, ConfigDiff};
+#[doc(hidden)]
+pub use dotted_key::{from_toml_value_into, to_toml_value};
+pub use env_interpolation::interpolate_env;
+pub use error::{Error, ErrorKind, ExitCodes, Result};
+#[doc(hidden)]
+pub use man_page::man_page;
+pub use migrate::{take_deprecation_warnings, DeprecatedKey};
+#[doc(hidden)]
+pub use migrate::{record_deprecated, rename_alias};
+pub use parse_outcome::ParseOutcome;
+pub use relative_path::resolve_relative_to_config;
+
+/// The config file path used to read a config document from stdin
+pub const STDIN_PATH: &str = "-";
 
 lazy_static! {
-    static ref DEFAULT_VALUE_STORE: SyncArena<OsString> = SyncArena::new();
+    // Stdin can only be consumed once so we cache its contents the first time it is read
+    static ref STDIN_CONTENTS: Mutex<Option<String>> = Mutex::new(None);
 }
 
-// This is very hacky. It reaches deep into clap internals to set the default values, but it works!
-// We need to set the defaults to prevent the CLI parsing from failing when a required argument is
-// not on the CLI but it is set in a config file.
-fn set_defaults_impl<'a>(
-    app: &mut App<'_, 'a>,
-    arg_path: &mut Vec<String>,
-    defaults: &impl ConfigOptArgToOsString,
-    arena: &'a impl Arena<OsString>,
-) {
-    for arg in &mut app.p.opts {
-        arg_path.push(String::from(arg.b.name));
-        if let Some(default) = defaults.arg_to_os_string(arg_path.as_slice()) {
-            arg.v.default_val = Some(arena.alloc(default));
-        }
-        arg_path.pop();
+// Read a config document. A path of `-` reads from stdin. Stdin is read at most once, later reads
+// return the same contents.
+fn read_config(path: &Path) -> io::Result<String> {
+    if path != Path::new(STDIN_PATH) {
+        return fs::read_to_string(path);
     }
-    for (_, arg) in &mut app.p.positionals {
-        arg_path.push(String::from(arg.b.name));
-        if let Some(default) = defaults.arg_to_os_string(arg_path.as_slice()) {
-            arg.v.default_val = Some(arena.alloc(default));
-        }
-        arg_path.pop();
+    let mut stdin_contents = STDIN_CONTENTS.lock().expect("stdin contents lock poisoned");
+    if let Some(contents) = stdin_contents.as_ref() {
+        return Ok(contents.clone());
     }
-    // Recursively set defaults for subcommands
-    for app in &mut app.p.subcommands {
-        arg_path.push(app.p.meta.name.clone());
-        set_defaults_impl(app, arg_path, defaults, arena);
-        arg_path.pop();
+    let mut contents = String::new();
+    io::stdin().read_to_string(&mut contents)?;
+    *stdin_contents = Some(contents.clone());
+    Ok(contents)
+}
+
+fn parse_config<T: DeserializeOwned>(format: ConfigFormat, contents: &str) -> io::Result<T> {
+    match format {
+        ConfigFormat::Toml => toml::from_str(contents).map_err(Into::into),
     }
 }
 
-/// CODO
+/// Deserialize a TOML config file.
+///
+/// A path of `-` reads the config from stdin. Other special files (eg `/dev/fd/3`) can be used to
+/// read from a file descriptor.
 pub fn from_toml_file<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T> {
     let path = path.as_ref();
+    let contents = read_config(path).map_err(|e| Error::ConfigFile(path.to_path_buf(), e))?;
+    parse_config(ConfigFormat::Toml, &contents)
+        .map_err(|e| Error::ConfigFile(path.to_path_buf(), e))
+}
+
+fn parse_value<T: DeserializeOwned>(value: toml::Value) -> io::Result<T> {
+    // A `toml::Value` only deserializes enums from strings, the table of a subcommand is only
+    // understood when the document is parsed
     let contents =
-        fs::read_to_string(path).map_err(|e| Error::ConfigFile(path.to_path_buf(), e))?;
-    toml::from_str(&contents).map_err(|e| Error::ConfigFile(path.to_path_buf(), e.into()))
+        toml::to_string(&value).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    parse_config(ConfigFormat::Toml, &contents)
+}
+
+/// Deserialize a TOML value tree read from the config file at `path`
+pub fn from_toml_value<T: DeserializeOwned>(path: impl AsRef<Path>, value: toml::Value) -> Result<T> {
+    parse_value(value).map_err(|e| Error::ConfigFile(path.as_ref().to_path_buf(), e))
+}
+
+/// Deserialize a config document from a string
+pub fn from_config_str<T: DeserializeOwned>(format: ConfigFormat, contents: &str) -> Result<T> {
+    parse_config(format, contents).map_err(Error::ConfigStr)
+}
+
+/// Deserialize a TOML value tree read from a config document string
+#[doc(hidden)]
+pub fn from_config_value<T: DeserializeOwned>(value: toml::Value) -> Result<T> {
+    parse_value(value).map_err(Error::ConfigStr)
+}
+
+/// Owns the default values given to the arguments of a `clap::App`
+///
+/// clap borrows the default value of an argument for as long as the `App` lives. The store is owned
+/// by the caller and must outlive every `App` using its values, which are freed when it is dropped.
+/// An `App` built again with a new store does not keep the values of the old one alive.
+pub struct DefaultValueStore(UnsyncArena<OsString>);
+
+impl DefaultValueStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self(UnsyncArena::new())
+    }
+
+    /// Set the default value of `arg` to the value of `arg_path` in `defaults`, storing the value
+    ///
+    /// `arg_path` is the names of the subcommands leading to the argument followed by its name.
+    pub fn default_value<'a, 'b>(
+        &'a self,
+        arg: Arg<'a, 'b>,
+        arg_path: &[String],
+        defaults: &impl ConfigOptArgToOsString,
+    ) -> Arg<'a, 'b> {
+        match defaults.arg_to_os_string(arg_path) {
+            Some(default) => arg.default_value_os(self.0.alloc(default)),
+            None => arg,
+        }
+    }
 }
 
-/// Set the defaults for a `clap::App`
-pub fn set_defaults(app: &mut App<'_, 'static>, defaults: &impl ConfigOptArgToOsString) {
-    let mut arg_path = Vec::new();
-    let arena = &*DEFAULT_VALUE_STORE;
-    set_defaults_impl(app, &mut arg_path, defaults, arena);
+impl Default for DefaultValueStore {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 fn filter_help<I>(iter: I) -> impl Iterator<Item = OsString>
@@ -82,7 +175,11 @@ where
 {
     iter.into_iter()
         .map(<I::Item as Into<OsString>>::into)
-        .filter(|a| a != "-h" && a != "--help")
+        .filter(|a| !is_help(a))
+}
+
+fn is_help(arg: &OsString) -> bool {
+    arg == "-h" || arg == "--help"
 }
 
 /// CODO
@@ -128,7 +225,7 @@ pub trait IgnoreHelp: StructOpt + Sized {
 }
 
 /// CODO
-pub trait ConfigOptType: ConfigOptArgToOsString + StructOpt {
+pub trait ConfigOptType: ConfigOptArgToOsString + StructOpt + Sized {
     /// If the `--generate-config` flag is set, return the current configuration.
     fn maybe_config_file(&self) -> Option<String>;
 
@@ -142,26 +239,383 @@ pub trait ConfigOptType: ConfigOptArgToOsString + StructOpt {
     }
 
     /// Patch with values from the `--config-files` argument
-    fn patch_with_config_files(&mut self) -> Result<&mut Self>;
+    fn patch_with_config_files(&mut self) -> Result<&mut Self> {
+        self.patch_with_config_files_recording(&mut Vec::new())
+    }
+
+    /// Patch with values from the `--config-files` argument, recording the values of each of this
+    /// type's own config files in the order they were merged
+    fn patch_with_config_files_recording(
+        &mut self,
+        files: &mut Vec<(PathBuf, Self)>,
+    ) -> Result<&mut Self>;
+
+    /// Get the paths of all config files read by `patch_with_config_files`
+    fn config_file_paths(&self) -> Vec<PathBuf>;
+
+    /// Should `--help` annotate each argument with its current value and where it came from
+    #[doc(hidden)]
+    fn annotate_help() -> bool {
+        false
+    }
+
+    /// The default config files if the `config` subcommand is enabled
+    #[doc(hidden)]
+    fn config_command_files() -> Option<Vec<PathBuf>> {
+        None
+    }
+
+    /// The name of the `--config-files` argument if the type has it
+    #[doc(hidden)]
+    fn config_files_arg_name() -> Option<&'static str> {
+        None
+    }
+
+    /// The dotted key of every value read from config files
+    #[doc(hidden)]
+    fn config_keys() -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Is the `--check-config` flag set
+    #[doc(hidden)]
+    fn check_config_requested(&self) -> bool {
+        false
+    }
+
+    /// Is the `--explain-config` flag set
+    #[doc(hidden)]
+    fn explain_config_requested(&self) -> bool {
+        false
+    }
+
+    /// Check every config file, including those of a subcommand, recording each problem
+    #[doc(hidden)]
+    fn check_config_files(&mut self, _problems: &mut Vec<String>) {}
+
+    /// Record the dotted key and message of each value failing its `validate` attributes
+    #[doc(hidden)]
+    fn validate_with_prefix(&self, _prefix: &[String], _failures: &mut Vec<(String, String)>) {}
+
+    /// Move the values of old key names in a config file to their current names, recording every
+    /// deprecated key by its path as written in the file
+    #[doc(hidden)]
+    fn rename_aliases_with_prefix(
+        _table: &mut toml::value::Table,
+        _prefix: &[String],
+        _written_prefix: &[String],
+        _deprecated: &mut Vec<DeprecatedKey>,
+    ) {
+    }
+
+    /// Rewrite the config file at `path` to use the current key names, returning the deprecated
+    /// keys it had.
+    ///
+    /// Keys renamed with `#[configopt(alias = "..")]` are moved to their current name, keeping
+    /// their comments where possible. Keys that are only deprecated are left in place.
+    fn migrate_config_file(path: impl AsRef<Path>) -> Result<Vec<DeprecatedKey>> {
+        migrate::migrate_config_file::<Self>(path.as_ref())
+    }
+
+    /// The version of the config file layout from `#[configopt(version = ..)]`. A config file
+    /// without the `config_version` key is taken to be version 1.
+    fn config_version() -> Option<u32> {
+        None
+    }
+
+    /// Upgrade the value tree of a config file to the current version with the functions of
+    /// `#[configopt(upgrade(..))]`, returning the older version it had
+    #[doc(hidden)]
+    fn upgrade_config_table(
+        _table: &mut toml::value::Table,
+    ) -> std::result::Result<Option<u32>, String> {
+        Ok(None)
+    }
+
+    /// Upgrade, rename and interpolate the values of a config document read from `path`, the same
+    /// as when parsing
+    #[doc(hidden)]
+    fn process_config_document(_value: &mut toml::Value, _path: &Path) -> Result<()> {
+        Ok(())
+    }
+
+    /// Rewrite the config file at `path` in the current version, returning the older version it
+    /// had or `None` if it was already current.
+    ///
+    /// Only the keys changed by the upgrades are written again, so the comments and formatting of
+    /// the other keys are kept.
+    fn upgrade_config_file(path: impl AsRef<Path>) -> Result<Option<u32>> {
+        config_version::upgrade_config_file::<Self>(path.as_ref())
+    }
+
+    /// Collect the arguments and their constraints. The arguments of each subcommand are pushed
+    /// to `groups` as they do not share a namespace with `group`.
+    #[doc(hidden)]
+    fn constraint_args_with_prefix(
+        &self,
+        _prefix: &[String],
+        _group: &mut Vec<constraints::ConstraintArg>,
+        _groups: &mut Vec<Vec<constraints::ConstraintArg>>,
+    ) {
+    }
+
+    /// Check the values against their `#[configopt(validate(..))]` attributes.
+    ///
+    /// `from_cli` holds the values set on the command line and `files` the values of each config
+    /// file as recorded by `patch_with_config_files_recording`. They name the layer that supplied an
+    /// invalid value.
+    fn validate(&self, from_cli: Option<&Self>, files: &[(PathBuf, Self)]) -> Result<()> {
+        validate::validate(self, from_cli, files)
+    }
 
     #[doc(hidden)]
     fn toml_config_with_prefix(&self, serde_prefix: &[String]) -> String;
 
+    /// Get the value at `path`, the outer `None` means the key does not exist
+    #[doc(hidden)]
+    fn get_with_path(&self, path: &[&str]) -> Option<Option<toml::Value>>;
+
+    /// Set the value at `path`, `None` means the key does not exist
+    #[doc(hidden)]
+    fn set_with_path(
+        &mut self,
+        path: &[&str],
+        value: toml::Value,
+    ) -> Option<std::result::Result<(), String>>;
+
+    /// Get the value of a dotted `key` such as `cmd3.flat_optional`.
+    ///
+    /// Keys are named as in the generated TOML configuration. The keys of a subcommand are nested
+    /// under the name of the subcommand. Returns `None` if the key is not set.
+    fn get_value(&self, key: &str) -> Result<Option<toml::Value>> {
+        self.get_with_path(&dotted_key::split_key(key))
+            .ok_or_else(|| Error::UnknownKey(String::from(key)))
+    }
+
+    /// Get the value of a dotted `key` as a `T`
+    fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        match self.get_value(key)? {
+            Some(value) => value
+                .try_into()
+                .map(Some)
+                .map_err(|e| Error::InvalidValue(String::from(key), e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    /// Set the value of a dotted `key`, failing if the value does not match the type of the key.
+    ///
+    /// Setting a key of another subcommand switches to that subcommand.
+    fn set(&mut self, key: &str, value: impl Into<toml::Value>) -> Result<()> {
+        match self.set_with_path(&dotted_key::split_key(key), value.into()) {
+            Some(Ok(())) => Ok(()),
+            Some(Err(e)) => Err(Error::InvalidValue(String::from(key), e)),
+            None => Err(Error::UnknownKey(String::from(key))),
+        }
+    }
+
     /// Generate TOML configuration.
     fn toml_config(&self) -> String {
-        self.toml_config_with_prefix(&[])
+        config_version::with_config_version(
+            Self::config_version(),
+            self.toml_config_with_prefix(&[]),
+        )
+    }
+}
+
+// Patch `configopt` with its config files, recording the values of each file, stopping if
+// `--generate-config` was set
+fn patch_with_config_files<T: ConfigOptType>(
+    configopt: &mut T,
+    files: &mut Vec<(PathBuf, T)>,
+) -> Result<()> {
+    configopt.patch_with_config_files_recording(files)?;
+    match configopt.maybe_config_file() {
+        Some(config) => Err(Error::ConfigGenerated(config)),
+        None => Ok(()),
+    }
+}
+
+// Run the `config` subcommand if it is enabled and on the command line, its output is returned as
+// an error like a generated config
+fn maybe_run_config_command<T: ConfigOptType + DeserializeOwned>(iter: &[OsString]) -> Result<()> {
+    let default_config_files = match T::config_command_files() {
+        Some(files) => files,
+        None => return Ok(()),
+    };
+    let (config_files, args) = match config_command::find(iter) {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+    let command = ConfigCommand::from_iter_safe(args)?;
+    let paths = if config_files.is_empty() {
+        default_config_files
+    } else {
+        config_files
+    };
+    Err(Error::ConfigCommand(command.run::<T>(&paths)?))
+}
+
+// Parse the command line as the configopt type, which has no required arguments or default values.
+// The matches hold only what the user set and whether the help was requested.
+fn command_line_matches<T: ConfigOpt>(
+    iter: &[OsString],
+) -> ClapResult<(ArgMatches<'static>, bool)> {
+    match T::ConfigOptType::clap().get_matches_from_safe(iter) {
+        Ok(matches) => Ok((matches, false)),
+        // Only filter the help after ensuring the help will be displayed. This avoids wrong
+        // behavior in which `--help` or `-h` are part of trailing arguments.
+        Err(e) if e.kind == ClapErrorKind::HelpDisplayed => T::ConfigOptType::clap()
+            .get_matches_from_safe(filter_help(iter))
+            .map(|matches| (matches, true))
+            .map_err(|_| e),
+        Err(e) => Err(e),
+    }
+}
+
+// The help is rendered for the actual app, it shows the default values of the app
+fn help_error<T: ConfigOpt>(iter: &[OsString]) -> Error {
+    match T::from_iter_safe(iter) {
+        Err(e) => e.into(),
+        Ok(_) => Error::ExpectedError(ClapError::with_description(
+            "the help was not displayed",
+            ClapErrorKind::HelpDisplayed,
+        )),
+    }
+}
+
+// The arguments with a value in the merged `configopt`, with the layer that supplied the value
+fn layered_args<T: ConfigOptType>(
+    configopt: &T,
+    from_cli: &T,
+    files: &[(PathBuf, T)],
+) -> Vec<(constraints::ConstraintArg, validate::ConfigLayer)> {
+    let mut group = Vec::new();
+    let mut groups = Vec::new();
+    configopt.constraint_args_with_prefix(&[], &mut group, &mut groups);
+    groups.insert(0, group);
+    groups
+        .into_iter()
+        .flatten()
+        .filter(|arg| matches!(configopt.get_value(&arg.key), Ok(Some(_))))
+        .map(|arg| {
+            let layer = validate::layer(&arg.key, Some(from_cli), files);
+            (arg, layer)
+        })
+        .collect()
+}
+
+// List the current value of each argument and where it came from, to follow the help of a type with
+// `#[configopt(annotate_help)]`
+fn current_values<T: ConfigOptType>(configopt: &T, from_cli: &T, files: &[(PathBuf, T)]) -> String {
+    let args = layered_args(configopt, from_cli, files);
+    let width = args
+        .iter()
+        .map(|(arg, _)| arg.usage.len())
+        .max()
+        .unwrap_or_default();
+    let mut section = String::new();
+    if !args.is_empty() {
+        section.push_str("\n\nCURRENT VALUES:");
+    }
+    for (arg, layer) in args {
+        section.push_str(&format!(
+            "\n    {:width$}    {} from {}",
+            arg.usage,
+            constraints::values(configopt, &arg.key).join(", "),
+            layer,
+            width = width
+        ));
+    }
+    section
+}
+
+// Explain the config for `--explain-config`, each key with its value and where the value came from
+fn explain<T: ConfigOptType>(configopt: &T, from_cli: &T, files: &[(PathBuf, T)]) -> String {
+    layered_args(configopt, from_cli, files)
+        .into_iter()
+        .filter_map(|(arg, layer)| {
+            let value = configopt.get_value(&arg.key).ok().flatten()?;
+            Some(format!("{} = {} # from {}", arg.key, value, layer))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Parse the command line once, patch it with the config files and let the actual app parse the
+// result
+fn try_from_iter_with_configopt_impl<T: ConfigOpt>(iter: &[OsString]) -> Result<T> {
+    let (matches, help) = match command_line_matches::<T>(iter) {
+        Ok(found) => found,
+        // The version is written by clap itself, so it is not requested from the actual app again
+        Err(e) if e.kind == ClapErrorKind::VersionDisplayed => return Err(e.into()),
+        Err(e) => {
+            // Prefer the same error from the actual app, its usage shows the required arguments.
+            // The actual app may also fail on a missing argument a config file would fill in.
+            return Err(match T::from_iter_safe(iter) {
+                Err(actual) if actual.kind == e.kind => actual.into(),
+                _ => e.into(),
+            });
+        }
+    };
+    let mut configopt = T::ConfigOptType::from_clap(&matches);
+    // The configopt type built from the same matches holds the values set on the command line
+    let from_cli = T::ConfigOptType::from_clap(&matches);
+    let mut files = Vec::new();
+    if help {
+        patch_with_config_files(&mut configopt, &mut files)?;
+        let mut error = help_error::<T>(iter);
+        if let Error::Clap(e) = &mut error {
+            if T::ConfigOptType::annotate_help() {
+                e.message
+                    .push_str(&current_values(&configopt, &from_cli, &files));
+            }
+        }
+        return Err(error);
+    }
+    // Missing required values are reported as problems when checking the config
+    if configopt.check_config_requested() {
+        return Err(check_config::check_config::<T>(iter, &matches, configopt));
+    }
+    // A config file given on the command line that does not exist is reported first
+    patch_with_config_files(&mut configopt, &mut files)?;
+    if configopt.explain_config_requested() {
+        let explanation = explain(&configopt, &from_cli, &files);
+        return Err(Error::ConfigExplained(explanation));
     }
+    // Values not set on the command line are filled in from the config files. The actual app
+    // validates them and fills in its own default values for the rest.
+    let app_iter = command_line::with_defaults(&T::clap(), iter, &matches, &configopt);
+    let app_matches = T::clap().get_matches_from_safe(app_iter);
+    if let Err(e) = &app_matches {
+        // Without a config file nothing can fill in the missing arguments, so clap's error with
+        // its usage is the most helpful
+        let readable = |path: &PathBuf| path == Path::new(STDIN_PATH) || path.exists();
+        if e.kind == ClapErrorKind::MissingRequiredArgument
+            && !configopt.config_file_paths().iter().any(readable)
+        {
+            return Err(app_matches.unwrap_err().into());
+        }
+    }
+    validate::validate(&configopt, Some(&from_cli), &files)?;
+    constraints::check(&configopt, &from_cli, &files)?;
+    let mut s = T::from_clap(&app_matches?);
+    // Take into account any values from config files by taking the values from the configopt
+    // type. We must use `take` instead of `patch` to be sure to override any default values with
+    // values from the config file.
+    T::take(&mut s, &mut configopt);
+    Ok(s)
 }
 
 /// CODO
 pub trait ConfigOpt: Sized + StructOpt {
-    type ConfigOptType: ConfigOptType + IgnoreHelp;
+    type ConfigOptType: ConfigOptType + IgnoreHelp + DeserializeOwned;
 
     /// Set argument default values then get the struct from the command line arguments.
     ///
     /// Print the error message and quit the program in case of failure.
     fn from_args_with_defaults(defaults: &impl ConfigOptArgToOsString) -> Self {
-        Self::try_from_args_with_defaults(defaults).unwrap_or_else(|e| e.exit())
+        Self::try_from_args_with_defaults(defaults).unwrap_or_else(|e| Error::from(e).exit())
     }
 
     /// Set argument default values then get the struct from the command line arguments.
@@ -181,7 +635,7 @@ pub trait ConfigOpt: Sized + StructOpt {
         I: IntoIterator,
         I::Item: Into<OsString> + Clone,
     {
-        Self::try_from_iter_with_defaults(iter, defaults).unwrap_or_else(|e| e.exit())
+        Self::try_from_iter_with_defaults(iter, defaults).unwrap_or_else(|e| Error::from(e).exit())
     }
 
     /// Set argument default values then get the struct from any iterator such as a Vec of your making.
@@ -197,13 +651,18 @@ pub trait ConfigOpt: Sized + StructOpt {
         I: IntoIterator,
         I::Item: Into<OsString> + Clone,
     {
-        let mut app = Self::clap();
-        // An arena allocator is used to extend the lifetimes of the default value strings.
-        let arena = UnsyncArena::new();
-        let mut arg_path = Vec::new();
-        set_defaults_impl(&mut app, &mut arg_path, defaults, &arena);
-        let matches = app.get_matches_from_safe(iter)?;
-        Ok(Self::from_clap(&matches))
+        let iter = iter.into_iter().map(Into::into).collect::<Vec<_>>();
+        let matches = match command_line_matches::<Self>(&iter) {
+            Ok((matches, false)) => matches,
+            // Get the help or error using the actual app
+            _ => return Self::from_iter_safe(&iter),
+        };
+        Self::from_iter_safe(command_line::with_defaults(
+            &Self::clap(),
+            &iter,
+            &matches,
+            defaults,
+        ))
     }
 
     /// Get the struct, taking into account config files, from the command line arguments.
@@ -246,36 +705,62 @@ pub trait ConfigOpt: Sized + StructOpt {
         I::Item: Into<OsString> + Clone,
     {
         let iter = iter.into_iter().map(Into::into).collect::<Vec<_>>();
-        // We ignore the help for the `configopt` type so the help message is rendered for the
-        // actual app.
-        match Self::ConfigOptType::try_from_iter_ignore_help(&iter) {
-            Ok(mut configopt) => {
-                configopt.patch_with_config_files()?;
-                if let Some(config) = configopt.maybe_config_file() {
-                    return Err(Error::ConfigGenerated(config));
-                }
-                // Take into account any values from config files by setting default values. This
-                // is needed so we do not get failures for missing arguments when they are really
-                // set in the config file.
-                let mut s = Self::try_from_iter_with_defaults(&iter, &configopt)?;
-                // Take into account any values from config files by taking the values from the
-                // configopt type. This is needed for types that do not always set their value if
-                // a default is set (eg Option<T>). We must use `take` instead of `patch` to be
-                // sure to override any default values with values from the config file.
-                <Self as ConfigOpt>::take(&mut s, &mut configopt);
-                Ok(s)
+        maybe_run_config_command::<Self::ConfigOptType>(&iter)?;
+        try_from_iter_with_configopt_impl(&iter)
+    }
+
+    /// Get the outcome of handling the command line arguments, taking into account config files.
+    ///
+    /// Unlike `try_from_args_with_configopt`, requests for help, the version, or a generated
+    /// config are returned as a [`ParseOutcome`](enum.ParseOutcome.html) instead of an error.
+    fn try_parse() -> Result<ParseOutcome<Self>> {
+        Self::try_parse_from(env::args())
+    }
+
+    /// Get the outcome of handling any iterator such as a Vec of your making, taking into account
+    /// config files.
+    ///
+    /// This never exits and nothing but the version, which clap 2 always prints, is written to
+    /// `stdout`. Only actual failures are returned as a `configopt::Error`.
+    fn try_parse_from<I>(iter: I) -> Result<ParseOutcome<Self>>
+    where
+        I: IntoIterator,
+        I::Item: Into<OsString> + Clone,
+    {
+        let iter = iter.into_iter().map(Into::into).collect::<Vec<_>>();
+        match maybe_run_config_command::<Self::ConfigOptType>(&iter) {
+            Ok(()) => {}
+            Err(Error::ConfigCommand(output)) => return Ok(ParseOutcome::ConfigCommand(output)),
+            Err(e) => return Err(e),
+        }
+        match try_from_iter_with_configopt_impl(&iter) {
+            Ok(s) => Ok(ParseOutcome::Parsed(s)),
+            Err(Error::ConfigGenerated(text)) => Ok(ParseOutcome::GeneratedConfig {
+                format: ConfigFormat::Toml,
+                text,
+            }),
+            Err(Error::ConfigCheck(problems)) if problems.is_empty() => {
+                Ok(ParseOutcome::ConfigChecked)
             }
-            Err(e) => {
-                // Get the error using the actual app
-                Self::from_iter_safe(&iter)?;
-                // We always expect an error to be generated. If we do not get an error return this
-                // error type. This helps with debugging. It would be confusing if ever returned an
-                // `Ok(Self)` with no config file information applied.
-                Err(Error::ExpectedError(e))
+            Err(Error::ConfigExplained(text)) => Ok(ParseOutcome::Explain(text)),
+            Err(Error::Clap(e)) if e.kind == ClapErrorKind::HelpDisplayed => {
+                Ok(ParseOutcome::Help(e.message))
             }
+            Err(Error::Clap(e)) if e.kind == ClapErrorKind::VersionDisplayed => Ok(
+                ParseOutcome::Version(parse_outcome::version_text(&Self::clap(), &iter)),
+            ),
+            Err(e) => Err(e),
         }
     }
 
+    /// Write a completion script for `shell` to `out`
+    ///
+    /// `--config-files` completes files with the extension of a supported config format, and the
+    /// keys of the `config` subcommand complete the dotted keys of the config file.
+    fn gen_completions_to(bin_name: &str, shell: Shell, out: &mut impl Write) -> io::Result<()> {
+        completions::generate::<Self::ConfigOptType>(&mut Self::clap(), bin_name, shell, out)
+    }
+
     /// CODO
     fn get_help(&self) -> String {
         let mut help = Vec::new();
@@ -298,4 +783,19 @@ pub trait ConfigOpt: Sized + StructOpt {
 
     /// Take each field from `other` and set it in `self`
     fn take(&mut self, other: &mut Self::ConfigOptType);
+
+    /// Compute the changes from `self` to `other`
+    fn diff(&self, other: &Self) -> ConfigDiff {
+        let mut changes = Vec::new();
+        self.diff_with_prefix(other, &[], &mut changes);
+        ConfigDiff(changes)
+    }
+
+    #[doc(hidden)]
+    fn diff_with_prefix(&self, other: &Self, prefix: &[String], changes: &mut Vec<ConfigChange>);
+
+    #[doc(hidden)]
+    fn variant_name(&self) -> Option<&'static str> {
+        None
+    }
 }
@@ -0,0 +1,171 @@
+use crate::{
+    arg_info::{app_args, ArgInfo},
+    reference::Reference,
+};
+use std::{fmt::Write, path::PathBuf};
+use structopt::clap::App;
+
+// Escape text so roff does not interpret it
+fn escape(s: &str) -> String {
+    let s = s.replace('\\', "\\e").replace('-', "\\-");
+    s.lines()
+        .map(|line| {
+            if line.starts_with('.') || line.starts_with('\'') {
+                format!("\\&{}", line)
+            } else {
+                String::from(line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn bold(s: &str) -> String {
+    format!("\\fB{}\\fR", escape(s))
+}
+
+fn italic(s: &str) -> String {
+    format!("\\fI{}\\fR", escape(s))
+}
+
+fn option_tag(name: &str, info: &ArgInfo) -> String {
+    if info.is_positional {
+        return italic(&format!("<{}>", name));
+    }
+    let mut flags = info
+        .short
+        .map(|s| bold(&format!("-{}", s)))
+        .into_iter()
+        .chain(info.long.as_ref().map(|l| bold(&format!("--{}", l))))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if !info.is_flag {
+        write!(flags, " {}", italic(&format!("<{}>", name))).unwrap();
+    }
+    flags
+}
+
+/// Render a roff man page for `app` with a CONFIGURATION section built from `reference` and a
+/// FILES section listing `files`
+#[doc(hidden)]
+pub fn man_page(app: &App<'_, '_>, reference: &Reference, files: &[PathBuf]) -> String {
+    let meta = &app.p.meta;
+    let name = &meta.name;
+    let mut result = format!(
+        ".TH \"{}\" 1 \"\" \"{} {}\"\n",
+        escape(&name.to_uppercase()),
+        escape(name),
+        escape(meta.version.unwrap_or(""))
+    );
+
+    result.push_str(".SH NAME\n");
+    match meta.about {
+        Some(about) => writeln!(result, "{} \\- {}", escape(name), escape(about)).unwrap(),
+        None => writeln!(result, "{}", escape(name)).unwrap(),
+    }
+
+    result.push_str(".SH SYNOPSIS\n");
+    write!(result, "{} [OPTIONS]", bold(name)).unwrap();
+    if !app.p.subcommands.is_empty() {
+        result.push_str(" [SUBCOMMAND]");
+    }
+    result.push('\n');
+
+    if let Some(about) = meta.long_about.or(meta.about) {
+        writeln!(result, ".SH DESCRIPTION\n{}", escape(about)).unwrap();
+    }
+
+    let args = app_args(app)
+        .into_iter()
+        .filter(|(_, info)| !info.hidden)
+        .collect::<Vec<_>>();
+    if !args.is_empty() {
+        result.push_str(".SH OPTIONS\n");
+        for (name, info) in args {
+            writeln!(result, ".TP\n{}", option_tag(&name, &info)).unwrap();
+            if !info.help.is_empty() {
+                writeln!(result, "{}", escape(&info.help)).unwrap();
+            }
+        }
+    }
+
+    let subcommands = app
+        .p
+        .subcommands
+        .iter()
+        .filter(|s| s.p.meta.name != "help")
+        .collect::<Vec<_>>();
+    if !subcommands.is_empty() {
+        result.push_str(".SH SUBCOMMANDS\n");
+        for subcommand in subcommands {
+            let meta = &subcommand.p.meta;
+            writeln!(result, ".TP\n{}", bold(&meta.name)).unwrap();
+            if let Some(about) = meta.about {
+                writeln!(result, "{}", escape(about)).unwrap();
+            }
+        }
+    }
+
+    let sections = reference
+        .sections
+        .iter()
+        .filter(|s| !s.entries.is_empty())
+        .collect::<Vec<_>>();
+    if !sections.is_empty() {
+        result.push_str(".SH CONFIGURATION\n");
+        result.push_str("Every key can also be set in a TOML config file.\n");
+        for section in sections {
+            let title = section.name.as_ref().unwrap_or(&section.command);
+            writeln!(result, ".SS {}", escape(title)).unwrap();
+            for entry in &section.entries {
+                writeln!(result, ".TP\n{} = {}", bold(&entry.key), italic(&entry.ty)).unwrap();
+                if !entry.help.is_empty() {
+                    writeln!(result, "{}\n.br", escape(&entry.help)).unwrap();
+                }
+                if let Some(flag) = &entry.flag {
+                    writeln!(result, "Flag: {}\n.br", bold(flag)).unwrap();
+                }
+                if let Some(env) = &entry.env {
+                    writeln!(result, "Environment: {}\n.br", bold(env)).unwrap();
+                }
+                if let Some(default_value) = &entry.default_value {
+                    writeln!(result, "Default: {}\n.br", escape(default_value)).unwrap();
+                }
+                if !entry.possible_values.is_empty() {
+                    writeln!(
+                        result,
+                        "Possible values: {}\n.br",
+                        escape(&entry.possible_values.join(", "))
+                    )
+                    .unwrap();
+                }
+            }
+        }
+    }
+
+    if !files.is_empty() {
+        result.push_str(".SH FILES\n");
+        for file in files {
+            writeln!(
+                result,
+                ".TP\n{}\nDefault config file",
+                italic(&file.to_string_lossy())
+            )
+            .unwrap();
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_roff() {
+        assert_eq!(escape("a-b"), "a\\-b");
+        assert_eq!(escape("C:\\dir"), "C:\\edir");
+        assert_eq!(escape(".start\n'quote"), "\\&.start\n\\&'quote");
+    }
+}
@@ -0,0 +1,215 @@
+use crate::{
+    dotted_key::{document_table_mut, remove_document_item},
+    ConfigOptType, Error, Result,
+};
+use std::{
+    cell::RefCell,
+    fmt, fs,
+    io::{Error as IoError, ErrorKind as IoErrorKind},
+    path::Path,
+};
+use toml::{value::Table, Value};
+use toml_edit::{DocumentMut, Item, Key, TableLike};
+
+thread_local! {
+    // The warnings not yet taken. Parsing reads config files more than once so each is kept once.
+    static WARNINGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A deprecated key found in a config file
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeprecatedKey {
+    /// The path of the key as written in the file
+    pub path: Vec<String>,
+    /// The path of the key it was renamed to with `#[configopt(alias = "..")]`
+    pub renamed_to: Option<Vec<String>>,
+    /// The note of `#[configopt(deprecated = "..")]`
+    pub note: Option<String>,
+}
+
+impl fmt::Display for DeprecatedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.renamed_to {
+            Some(renamed_to) => write!(
+                f,
+                "Config key '{}' was renamed to '{}'",
+                self.path.join("."),
+                renamed_to.join(".")
+            )?,
+            None => write!(f, "Config key '{}' is deprecated", self.path.join("."))?,
+        }
+        if let Some(note) = &self.note {
+            write!(f, ", {}", note)?;
+        }
+        Ok(())
+    }
+}
+
+/// Record a warning about each deprecated key of the config file at `path`
+#[doc(hidden)]
+pub fn record_deprecated(path: &Path, keys: &[DeprecatedKey]) {
+    WARNINGS.with(|warnings| {
+        let mut warnings = warnings.borrow_mut();
+        for key in keys {
+            let warning = format!("{} in file '{}'", key, path.display());
+            if !warnings.contains(&warning) {
+                warnings.push(warning);
+            }
+        }
+    });
+}
+
+/// Take the warnings about deprecated keys in the config files read so far on this thread.
+///
+/// Nothing is printed about deprecated keys, so call this after parsing to show them.
+pub fn take_deprecation_warnings() -> Vec<String> {
+    WARNINGS.with(|warnings| warnings.borrow_mut().drain(..).collect())
+}
+
+/// Replace the first part of a dotted key path if it is an old name of a field
+#[doc(hidden)]
+pub fn rename_alias<'a>(path: &[&'a str], aliases: &[(&str, &'a str)]) -> Vec<&'a str> {
+    let mut path = path.to_vec();
+    if let Some(first) = path.first_mut() {
+        if let Some((_, name)) = aliases.iter().find(|(alias, _)| *alias == *first) {
+            *first = name;
+        }
+    }
+    path
+}
+
+fn contains_path(table: &Table, path: &[String]) -> bool {
+    match path.split_first() {
+        Some((first, [])) => table.contains_key(first),
+        Some((first, rest)) => match table.get(first) {
+            Some(Value::Table(table)) => contains_path(table, rest),
+            _ => false,
+        },
+        None => false,
+    }
+}
+
+// Insert `item` at the end of `table`, keeping the comments of `key`
+fn insert_formatted(table: &mut dyn TableLike, key: &Key, name: &str, item: Item) {
+    table.insert(name, item);
+    if let Some(mut inserted) = table.key_mut(name) {
+        *inserted.leaf_decor_mut() = key.leaf_decor().clone();
+    }
+}
+
+// Move the key at `from` to `to` with its comments, returning whether it was set. A key renamed
+// within its table keeps its place.
+fn rename(document: &mut DocumentMut, from: &[String], to: &[String]) -> bool {
+    let (from_name, from_table) = match from.split_last() {
+        Some(split) => split,
+        None => return false,
+    };
+    let (name, table) = match to.split_last() {
+        Some(split) => split,
+        None => return false,
+    };
+    let following = match document_table_mut(document, from_table, false) {
+        Some(t) if from_table == table => t
+            .iter()
+            .map(|(k, _)| String::from(k))
+            .skip_while(|k| k != from_name)
+            .skip(1)
+            .collect(),
+        _ => Vec::new(),
+    };
+    let (key, item) = match remove_document_item(document, from) {
+        Some(removed) => removed,
+        None => return false,
+    };
+    let table = document_table_mut(document, table, true).expect("the table to be created");
+    insert_formatted(table, &key, name, item);
+    for name in following {
+        if let Some(key) = table.key(&name).cloned() {
+            if let Some(item) = table.remove(&name) {
+                insert_formatted(table, &key, &name, item);
+            }
+        }
+    }
+    true
+}
+
+/// Rewrite a config document into the current layout of `T`, returning the new document and
+/// the deprecated keys it had
+pub(crate) fn migrate_config_str<T: ConfigOptType>(
+    contents: &str,
+) -> Result<(String, Vec<DeprecatedKey>)> {
+    let original = toml::from_str::<Table>(contents).map_err(|e| Error::ConfigStr(e.into()))?;
+    let mut table = original.clone();
+    let mut deprecated = Vec::new();
+    T::rename_aliases_with_prefix(&mut table, &[], &[], &mut deprecated);
+    let mut document = contents
+        .parse::<DocumentMut>()
+        .map_err(|e| Error::ConfigStr(IoError::new(IoErrorKind::InvalidData, e.to_string())))?;
+    let mut renamed: Vec<(&[String], &[String])> = Vec::new();
+    for key in &deprecated {
+        if let Some(renamed_to) = &key.renamed_to {
+            // A key is reported as written, but a table holding it may already be renamed
+            let path = renamed
+                .iter()
+                .rev()
+                .find(|&&(from, _)| key.path.starts_with(from))
+                .map_or_else(
+                    || key.path.clone(),
+                    |&(from, to)| [to, &key.path[from.len()..]].concat(),
+                );
+            // Like when reading the file, the value of the new key takes precedence
+            if contains_path(&original, renamed_to) {
+                remove_document_item(&mut document, &path);
+            } else {
+                rename(&mut document, &path, renamed_to);
+                renamed.push((key.path.as_slice(), renamed_to.as_slice()));
+            }
+        }
+    }
+    Ok((document.to_string(), deprecated))
+}
+
+pub(crate) fn migrate_config_file<T: ConfigOptType>(path: &Path) -> Result<Vec<DeprecatedKey>> {
+    let to_error = |e: IoError| Error::ConfigFile(path.to_path_buf(), e);
+    let contents = fs::read_to_string(path).map_err(to_error)?;
+    let (migrated, deprecated) = migrate_config_str::<T>(&contents).map_err(|e| match e {
+        Error::ConfigStr(e) => to_error(e),
+        e => e,
+    })?;
+    if migrated != contents {
+        fs::write(path, migrated).map_err(to_error)?;
+    }
+    Ok(deprecated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rename_alias_first_part() {
+        let aliases = [("old", "new")];
+        assert_eq!(vec!["new", "old"], rename_alias(&["old", "old"], &aliases));
+        assert_eq!(vec!["other"], rename_alias(&["other"], &aliases));
+        assert!(rename_alias(&[], &aliases).is_empty());
+    }
+
+    #[test]
+    fn deprecated_key_message() {
+        let key = DeprecatedKey {
+            path: vec![String::from("server"), String::from("addr")],
+            renamed_to: Some(vec![String::from("server"), String::from("host")]),
+            note: Some(String::from("use host")),
+        };
+        assert_eq!(
+            "Config key 'server.addr' was renamed to 'server.host', use host",
+            key.to_string()
+        );
+        let key = DeprecatedKey {
+            path: vec![String::from("legacy")],
+            renamed_to: None,
+            note: None,
+        };
+        assert_eq!("Config key 'legacy' is deprecated", key.to_string());
+    }
+}
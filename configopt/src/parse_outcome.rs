@@ -0,0 +1,116 @@
+use crate::ConfigFormat;
+use std::{ffi::OsString, path::Path};
+use structopt::clap::{App, AppSettings};
+
+/// The result of successfully handling a command line
+///
+/// Requests for help, the version, a generated config, running the `config` subcommand, checking a
+/// valid config, or explaining the config are not failures, so they are returned as an outcome instead of an error. The
+/// process does not exit and only the version is written to `stdout`, by clap itself.
+#[derive(Debug, PartialEq)]
+pub enum ParseOutcome<T> {
+    /// The command line was parsed and patched with the config files
+    Parsed(T),
+    /// `--help` was requested
+    Help(String),
+    /// `--version` was requested
+    Version(String),
+    /// `--generate-config` was requested
+    GeneratedConfig { format: ConfigFormat, text: String },
+    /// The `config` subcommand ran, holding its output
+    ConfigCommand(String),
+    /// `--check-config` was requested and the config is valid
+    ConfigChecked,
+    /// `--explain-config` was requested, holding each value and where it came from
+    Explain(String),
+}
+
+impl<T> ParseOutcome<T> {
+    /// Get the parsed value, if the command line was parsed
+    pub fn parsed(self) -> Option<T> {
+        match self {
+            Self::Parsed(parsed) => Some(parsed),
+            _ => None,
+        }
+    }
+}
+
+fn find_subcommand<'a, 'b, 'c>(app: &'c App<'a, 'b>, name: &str) -> Option<&'c App<'a, 'b>> {
+    app.p.subcommands.iter().find(|s| {
+        s.p.meta.name == name
+            || s.p
+                .meta
+                .aliases
+                .iter()
+                .flatten()
+                .any(|(alias, _)| *alias == name)
+    })
+}
+
+// clap 2 writes the version to `stdout` itself and its `VersionDisplayed` error does not hold it.
+// The text is rendered again from the app of the subcommand it was requested for.
+pub(crate) fn version_text(app: &App<'_, '_>, args: &[OsString]) -> String {
+    let bin_name = args
+        .first()
+        .and_then(|a| Path::new(a).file_name())
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| app.p.meta.name.clone());
+    let mut app = app;
+    let mut subcommands = Vec::new();
+    let mut inherited = None;
+    let mut use_long = false;
+    for arg in args.iter().skip(1).filter_map(|a| a.to_str()) {
+        if arg == "--" {
+            break;
+        }
+        use_long |= arg == "--version";
+        if let Some(subcommand) = find_subcommand(app, arg) {
+            if app.p.is_set(AppSettings::GlobalVersion) {
+                inherited = app.p.meta.version.or(inherited);
+            }
+            subcommands.push(subcommand.p.meta.name.as_str());
+            app = subcommand;
+        }
+    }
+    let meta = &app.p.meta;
+    let version = if use_long {
+        meta.long_version.or(meta.version)
+    } else {
+        meta.version.or(meta.long_version)
+    };
+    let version = version.or(inherited).unwrap_or("");
+    if subcommands.is_empty() {
+        format!("{} {}", meta.name, version)
+    } else {
+        format!("{}-{} {}", bin_name, subcommands.join("-"), version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use structopt::clap::{Arg, SubCommand};
+
+    fn app() -> App<'static, 'static> {
+        App::new("app")
+            .version("1.0.0")
+            .long_version("1.0.0 (long)")
+            .arg(Arg::with_name("verbose").short("v"))
+            .subcommand(SubCommand::with_name("run").version("2.0.0").alias("r"))
+    }
+
+    fn version(args: &[&str]) -> String {
+        let args = args.iter().map(OsString::from).collect::<Vec<_>>();
+        version_text(&app(), &args)
+    }
+
+    #[test]
+    fn version_texts() {
+        assert_eq!(version(&["app", "-V"]), "app 1.0.0");
+        assert_eq!(
+            version(&["/bin/app", "-v", "--version"]),
+            "app 1.0.0 (long)"
+        );
+        assert_eq!(version(&["/bin/app", "r", "-V"]), "app-run 2.0.0");
+    }
+}
@@ -0,0 +1,329 @@
+//! A reference document of every config key
+use crate::arg_info::ArgInfo;
+use std::fmt::Write;
+use structopt::clap::App;
+
+/// A single config key
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    /// The dotted path of the key
+    pub key: String,
+    /// A human readable type of the value
+    pub ty: String,
+    pub default_value: Option<String>,
+    pub help: String,
+    /// The CLI flag(s) or positional value name that set the key
+    pub flag: Option<String>,
+    pub env: Option<String>,
+    pub possible_values: Vec<String>,
+}
+
+impl Entry {
+    #[doc(hidden)]
+    pub fn new(key: String, ty: &str, info: ArgInfo) -> Self {
+        let flag = if info.is_positional {
+            Some(format!("<{}>", key.rsplit('.').next().unwrap_or(&key)))
+        } else {
+            let flags = info
+                .long
+                .map(|l| format!("--{}", l))
+                .into_iter()
+                .chain(info.short.map(|s| format!("-{}", s)))
+                .collect::<Vec<_>>();
+            if flags.is_empty() {
+                None
+            } else {
+                Some(flags.join(", "))
+            }
+        };
+        let default_value = match info.default_value {
+            None if info.is_flag => Some(String::from("false")),
+            default_value => default_value,
+        };
+        Self {
+            key,
+            ty: String::from(ty),
+            default_value,
+            help: info.help,
+            flag,
+            env: info.env,
+            possible_values: info.possible_values,
+        }
+    }
+}
+
+/// The keys of a subcommand or of a flattened struct
+#[derive(Clone, Debug, PartialEq)]
+pub struct Section {
+    /// The full command, for example `myapp serve`
+    pub command: String,
+    /// The dotted name of the flattened field this section documents, if any
+    pub name: Option<String>,
+    pub about: String,
+    pub entries: Vec<Entry>,
+}
+
+impl Section {
+    /// The section of the command described by `app`
+    #[doc(hidden)]
+    pub fn command(app: &App<'_, '_>, parent: Option<&str>) -> Self {
+        let command = match parent {
+            Some(parent) => format!("{} {}", parent, app.p.meta.name),
+            None => app.p.meta.name.clone(),
+        };
+        Self {
+            command,
+            name: None,
+            about: about(app),
+            entries: Vec::new(),
+        }
+    }
+
+    /// The section of the flattened field `name` of `parent`
+    #[doc(hidden)]
+    pub fn flattened(parent: &Section, name: &str) -> Self {
+        let name = match &parent.name {
+            Some(parent) => format!("{}.{}", parent, name),
+            None => String::from(name),
+        };
+        Self {
+            command: parent.command.clone(),
+            name: Some(name),
+            about: String::new(),
+            entries: Vec::new(),
+        }
+    }
+
+    fn title(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.command)
+    }
+}
+
+/// A reference document of every config key grouped by subcommand and flattened section
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reference {
+    pub title: String,
+    pub about: String,
+    pub sections: Vec<Section>,
+}
+
+fn about(app: &App<'_, '_>) -> String {
+    String::from(app.p.meta.long_about.or(app.p.meta.about).unwrap_or(""))
+}
+
+fn escape_markdown(s: &str) -> String {
+    s.replace('|', "\\|").replace('\n', "<br>")
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl Reference {
+    #[doc(hidden)]
+    pub fn new(app: &App<'_, '_>, sections: Vec<Section>) -> Self {
+        Self {
+            title: app.p.meta.name.clone(),
+            about: about(app),
+            sections,
+        }
+    }
+
+    fn sections(&self) -> impl Iterator<Item = &Section> {
+        self.sections.iter().filter(|s| !s.entries.is_empty())
+    }
+
+    /// Render the reference as Markdown
+    pub fn to_markdown(&self) -> String {
+        let code = |s: &Option<String>| {
+            s.as_ref()
+                .map(|s| format!("`{}`", escape_markdown(s)))
+                .unwrap_or_default()
+        };
+        let mut result = format!("# {}\n\n", self.title);
+        if !self.about.is_empty() {
+            writeln!(result, "{}\n", self.about).unwrap();
+        }
+        for section in self.sections() {
+            let level = if section.name.is_some() { "###" } else { "##" };
+            writeln!(result, "{} `{}`\n", level, section.title()).unwrap();
+            if !section.about.is_empty() {
+                writeln!(result, "{}\n", section.about).unwrap();
+            }
+            result.push_str("| Key | Type | Default | Flag | Env | Description |\n");
+            result.push_str("| --- | --- | --- | --- | --- | --- |\n");
+            for entry in &section.entries {
+                let mut help = escape_markdown(&entry.help);
+                if !entry.possible_values.is_empty() {
+                    if !help.is_empty() {
+                        help.push_str("<br>");
+                    }
+                    let values = entry
+                        .possible_values
+                        .iter()
+                        .map(|v| format!("`{}`", escape_markdown(v)))
+                        .collect::<Vec<_>>();
+                    write!(help, "Possible values: {}", values.join(", ")).unwrap();
+                }
+                writeln!(
+                    result,
+                    "| `{}` | {} | {} | {} | {} | {} |",
+                    entry.key,
+                    escape_markdown(&entry.ty),
+                    code(&entry.default_value),
+                    code(&entry.flag),
+                    code(&entry.env),
+                    help
+                )
+                .unwrap();
+            }
+            result.push('\n');
+        }
+        result
+    }
+
+    /// Render the reference as a standalone HTML document
+    pub fn to_html(&self) -> String {
+        let code = |s: &Option<String>| {
+            s.as_ref()
+                .map(|s| format!("<code>{}</code>", escape_html(s)))
+                .unwrap_or_default()
+        };
+        let title = escape_html(&self.title);
+        let mut result = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n<h1>{}</h1>\n",
+            title, title
+        );
+        if !self.about.is_empty() {
+            writeln!(result, "<p>{}</p>", escape_html(&self.about)).unwrap();
+        }
+        for section in self.sections() {
+            let level = if section.name.is_some() { "h3" } else { "h2" };
+            writeln!(
+                result,
+                "<{}><code>{}</code></{}>",
+                level,
+                escape_html(section.title()),
+                level
+            )
+            .unwrap();
+            if !section.about.is_empty() {
+                writeln!(result, "<p>{}</p>", escape_html(&section.about)).unwrap();
+            }
+            result.push_str("<table>\n<tr><th>Key</th><th>Type</th><th>Default</th><th>Flag</th><th>Env</th><th>Description</th></tr>\n");
+            for entry in &section.entries {
+                let mut help = escape_html(&entry.help).replace('\n', "<br>");
+                if !entry.possible_values.is_empty() {
+                    if !help.is_empty() {
+                        help.push_str("<br>");
+                    }
+                    let values = entry
+                        .possible_values
+                        .iter()
+                        .map(|v| format!("<code>{}</code>", escape_html(v)))
+                        .collect::<Vec<_>>();
+                    write!(help, "Possible values: {}", values.join(", ")).unwrap();
+                }
+                writeln!(
+                    result,
+                    "<tr><td><code>{}</code></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    escape_html(&entry.key),
+                    escape_html(&entry.ty),
+                    code(&entry.default_value),
+                    code(&entry.flag),
+                    code(&entry.env),
+                    help
+                )
+                .unwrap();
+            }
+            result.push_str("</table>\n");
+        }
+        result.push_str("</body>\n</html>\n");
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference() -> Reference {
+        Reference {
+            title: String::from("app"),
+            about: String::from("My app"),
+            sections: vec![
+                Section {
+                    command: String::from("app"),
+                    name: None,
+                    about: String::new(),
+                    entries: vec![Entry {
+                        key: String::from("level"),
+                        ty: String::from("string"),
+                        default_value: Some(String::from("info")),
+                        help: String::from("The log level | verbosity"),
+                        flag: Some(String::from("--level, -l")),
+                        env: Some(String::from("APP_LEVEL")),
+                        possible_values: vec![String::from("debug"), String::from("info")],
+                    }],
+                },
+                Section {
+                    command: String::from("app serve"),
+                    name: None,
+                    about: String::from("Serve <things>"),
+                    entries: Vec::new(),
+                },
+                Section {
+                    command: String::from("app"),
+                    name: Some(String::from("server")),
+                    about: String::new(),
+                    entries: vec![Entry {
+                        key: String::from("server.port"),
+                        ty: String::from("integer"),
+                        default_value: None,
+                        help: String::new(),
+                        flag: Some(String::from("--port")),
+                        env: None,
+                        possible_values: Vec::new(),
+                    }],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn render_markdown() {
+        assert_eq!(
+            reference().to_markdown(),
+            "# app
+
+My app
+
+## `app`
+
+| Key | Type | Default | Flag | Env | Description |
+| --- | --- | --- | --- | --- | --- |
+| `level` | string | `info` | `--level, -l` | `APP_LEVEL` | The log level \\| verbosity<br>Possible values: `debug`, `info` |
+
+### `server`
+
+| Key | Type | Default | Flag | Env | Description |
+| --- | --- | --- | --- | --- | --- |
+| `server.port` | integer |  | `--port` |  |  |
+
+"
+        );
+    }
+
+    #[test]
+    fn render_html() {
+        let html = reference().to_html();
+        assert!(html.contains("<title>app</title>"));
+        assert!(html.contains("<h2><code>app</code></h2>"));
+        assert!(html.contains("<h3><code>server</code></h3>"));
+        assert!(html.contains("<td><code>server.port</code></td><td>integer</td>"));
+        assert!(!html.contains("app serve"));
+    }
+}
@@ -0,0 +1,93 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+use toml::Value;
+
+/// Resolve relative paths in `value` against the directory of the config file at `config_path`.
+///
+/// `value` can be a single path or an array of paths. If `expand_tilde` is set a leading `~` is
+/// replaced with the `HOME` directory before resolving.
+pub fn resolve_relative_to_config(value: &mut Value, config_path: &Path, expand_tilde: bool) {
+    let home = if expand_tilde {
+        env::var_os("HOME").map(PathBuf::from)
+    } else {
+        None
+    };
+    resolve(value, config_path, expand_tilde, home.as_deref());
+}
+
+fn resolve(value: &mut Value, config_path: &Path, expand_tilde: bool, home: Option<&Path>) {
+    match value {
+        Value::String(s) => {
+            let mut path = PathBuf::from(&s);
+            if expand_tilde {
+                path = expand_home(&path, home);
+            }
+            if path.is_relative() {
+                if let Some(dir) = config_path.parent() {
+                    path = dir.join(path);
+                }
+            }
+            *s = path.to_string_lossy().into_owned();
+        }
+        Value::Array(values) => {
+            for value in values {
+                resolve(value, config_path, expand_tilde, home);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn expand_home(path: &Path, home: Option<&Path>) -> PathBuf {
+    match (path.strip_prefix("~"), home) {
+        (Ok(rest), Some(home)) if rest.as_os_str().is_empty() => home.to_path_buf(),
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve_path(path: &str, expand_tilde: bool) -> Value {
+        let mut value = Value::String(String::from(path));
+        let home = Path::new("/home/user");
+        resolve(&mut value, Path::new("/etc/app/config.toml"), expand_tilde, Some(home));
+        value
+    }
+
+    #[test]
+    fn resolve_relative_paths() {
+        assert_eq!(resolve_path("data/db", false).as_str(), Some("/etc/app/data/db"));
+        assert_eq!(resolve_path("../db", false).as_str(), Some("/etc/app/../db"));
+        assert_eq!(resolve_path("/var/db", false).as_str(), Some("/var/db"));
+        assert_eq!(resolve_path("~/db", false).as_str(), Some("/etc/app/~/db"));
+
+        let mut value = Value::Array(vec![
+            Value::String(String::from("a")),
+            Value::String(String::from("/b")),
+        ]);
+        resolve_relative_to_config(&mut value, Path::new("config.toml"), false);
+        assert_eq!(
+            value,
+            Value::Array(vec![
+                Value::String(String::from("a")),
+                Value::String(String::from("/b")),
+            ])
+        );
+    }
+
+    #[test]
+    fn resolve_tilde() {
+        assert_eq!(resolve_path("~/db", true).as_str(), Some("/home/user/db"));
+        assert_eq!(resolve_path("~", true).as_str(), Some("/home/user"));
+        assert_eq!(resolve_path("~other/db", true).as_str(), Some("/etc/app/~other/db"));
+
+        let mut value = Value::String(String::from("~/db"));
+        resolve(&mut value, Path::new("/etc/app/config.toml"), true, None);
+        assert_eq!(value.as_str(), Some("/etc/app/~/db"));
+    }
+}
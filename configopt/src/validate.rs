@@ -0,0 +1,212 @@
+//! Checks used by `#[configopt(validate(..))]`
+//!
+//! Each check returns a message describing why the value is invalid. A function used with
+//! `validate(with = path::to::function)` has the same signature, taking a reference to the value.
+
+use crate::{ConfigOptType, Error, Result};
+use regex::Regex;
+use std::{
+    collections::{BTreeMap, HashMap},
+    ffi::{OsStr, OsString},
+    fmt::{self, Debug},
+    ops::RangeBounds,
+    path::{Path, PathBuf},
+};
+
+/// The value is within `range`
+pub fn range<T, R>(value: &T, range: R) -> std::result::Result<(), String>
+where
+    T: PartialOrd + Debug,
+    R: RangeBounds<T> + Debug,
+{
+    if range.contains(value) {
+        Ok(())
+    } else {
+        Err(format!("{:?} is not in the range {:?}", value, range))
+    }
+}
+
+/// The whole value matches the regular expression `pattern`
+pub fn regex(value: &impl AsRef<OsStr>, pattern: &str) -> std::result::Result<(), String> {
+    let regex = Regex::new(&format!("^(?:{})$", pattern))
+        .map_err(|e| format!("invalid regular expression '{}', err: {}", pattern, e))?;
+    let value = value.as_ref().to_string_lossy();
+    if regex.is_match(&value) {
+        Ok(())
+    } else {
+        Err(format!("'{}' does not match '{}'", value, pattern))
+    }
+}
+
+/// A value that can be empty
+pub trait IsEmpty {
+    fn is_empty(&self) -> bool;
+}
+
+// The inherent `is_empty` is called by path, as method call syntax would resolve to this trait
+macro_rules! impl_is_empty {
+    ($($ty:ty),*) => {
+        $(impl IsEmpty for $ty {
+            fn is_empty(&self) -> bool {
+                <$ty>::is_empty(self)
+            }
+        })*
+    };
+}
+
+impl_is_empty!(String, str, OsStr);
+
+impl IsEmpty for OsString {
+    fn is_empty(&self) -> bool {
+        self.as_os_str().is_empty()
+    }
+}
+
+impl IsEmpty for PathBuf {
+    fn is_empty(&self) -> bool {
+        self.as_os_str().is_empty()
+    }
+}
+
+impl<T> IsEmpty for Vec<T> {
+    fn is_empty(&self) -> bool {
+        <[T]>::is_empty(self)
+    }
+}
+
+impl<K, V> IsEmpty for HashMap<K, V> {
+    fn is_empty(&self) -> bool {
+        HashMap::is_empty(self)
+    }
+}
+
+impl<K, V> IsEmpty for BTreeMap<K, V> {
+    fn is_empty(&self) -> bool {
+        BTreeMap::is_empty(self)
+    }
+}
+
+/// The value is not empty
+pub fn non_empty(value: &(impl IsEmpty + ?Sized)) -> std::result::Result<(), String> {
+    if value.is_empty() {
+        Err(String::from("the value is empty"))
+    } else {
+        Ok(())
+    }
+}
+
+/// The path exists
+pub fn path_exists(value: &impl AsRef<Path>) -> std::result::Result<(), String> {
+    let path = value.as_ref();
+    if path.exists() {
+        Ok(())
+    } else {
+        Err(format!("'{}' does not exist", path.display()))
+    }
+}
+
+/// Where a config value came from
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigLayer {
+    CommandLine,
+    File(PathBuf),
+    /// Not set on the command line or in a config file
+    Default,
+}
+
+impl fmt::Display for ConfigLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CommandLine => write!(f, "the command line"),
+            Self::File(path) => write!(f, "file '{}'", path.display()),
+            Self::Default => write!(f, "the default value"),
+        }
+    }
+}
+
+/// A value that failed a `#[configopt(validate(..))]` check
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationError {
+    /// The dotted key of the value
+    pub key: String,
+    /// The layer that supplied the value
+    pub layer: ConfigLayer,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid value for config key '{}' from {}, err: {}",
+            self.key, self.layer, self.message
+        )
+    }
+}
+
+// The highest precedence layer setting `key`
+pub(crate) fn layer<T: ConfigOptType>(key: &str, from_cli: Option<&T>, files: &[(PathBuf, T)]) -> ConfigLayer {
+    let is_set = |values: &T| matches!(values.get_value(key), Ok(Some(_)));
+    if from_cli.is_some_and(is_set) {
+        return ConfigLayer::CommandLine;
+    }
+    files
+        .iter()
+        .rev()
+        .find(|(_, values)| is_set(values))
+        .map_or(ConfigLayer::Default, |(path, _)| {
+            ConfigLayer::File(path.clone())
+        })
+}
+
+/// Run the validators of the merged `configopt`. `from_cli` and `files` are the layers it was merged
+/// from, they name the layer that supplied an invalid value.
+pub(crate) fn validate<T: ConfigOptType>(
+    configopt: &T,
+    from_cli: Option<&T>,
+    files: &[(PathBuf, T)],
+) -> Result<()> {
+    let mut failures = Vec::new();
+    configopt.validate_with_prefix(&[], &mut failures);
+    if failures.is_empty() {
+        return Ok(());
+    }
+    let errors = failures
+        .into_iter()
+        .map(|(key, message)| ValidationError {
+            layer: layer(&key, from_cli, files),
+            key,
+            message,
+        })
+        .collect();
+    Err(Error::Validation(errors))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks() {
+        assert!(range(&80u16, 1..=65535).is_ok());
+        assert_eq!(
+            Err(String::from("0 is not in the range 1..=65535")),
+            range(&0u16, 1..=65535)
+        );
+        assert!(range(&0.5, ..1.0).is_ok());
+
+        assert!(regex(&String::from("info"), "debug|info").is_ok());
+        assert!(regex(&String::from("information"), "debug|info").is_err());
+        assert!(regex(&PathBuf::from("a.toml"), r".*\.toml").is_ok());
+        assert!(regex(&String::from("a"), "(").is_err());
+
+        assert!(non_empty(&String::from("a")).is_ok());
+        assert!(non_empty(&String::new()).is_err());
+        assert!(non_empty(&Vec::<u8>::new()).is_err());
+        assert!(non_empty(&OsString::from("a")).is_ok());
+        assert!(non_empty(OsStr::new("")).is_err());
+
+        assert!(path_exists(&PathBuf::from(".")).is_ok());
+        assert!(path_exists(&PathBuf::from("does/not/exist")).is_err());
+    }
+}
@@ -0,0 +1,154 @@
+//! Reload the configuration when config files change.
+//!
+//! The watcher polls the modification times of all config files (the default config files and
+//! any `--config-files`). When a change is detected, and no further changes happen for the
+//! debounce period, the full configuration is rebuilt with
+//! [`try_from_iter_with_configopt`](../trait.ConfigOpt.html#method.try_from_iter_with_configopt)
+//! using the original CLI arguments. This keeps the CLI arguments as the top layer.
+
+use crate::{ConfigOpt, ConfigOptType, IgnoreHelp, Result, STDIN_PATH};
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, SystemTime},
+};
+
+/// Options controlling how config files are watched
+#[derive(Clone, Copy, Debug)]
+pub struct WatchOptions {
+    /// How often the config files are checked for changes
+    pub poll_interval: Duration,
+    /// How long the config files must be unchanged before the configuration is reloaded
+    pub debounce: Duration,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(1),
+            debounce: Duration::from_millis(250),
+        }
+    }
+}
+
+/// A handle to a running config file watcher. The watcher is stopped when this is dropped.
+pub struct ConfigWatcher {
+    paths: Vec<PathBuf>,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl ConfigWatcher {
+    /// The config files being watched
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Stop watching and wait for the watcher thread to exit
+    pub fn stop(mut self) {
+        self.stop_and_join();
+    }
+
+    fn stop_and_join(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            handle.join().ok();
+        }
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.stop_and_join();
+    }
+}
+
+// The modification time of each file, `None` if the file does not exist
+fn snapshot(paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    paths
+        .iter()
+        .map(|p| fs::metadata(p).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+/// Watch the config files of `T` and call `callback` with the reloaded configuration each time
+/// they change.
+///
+/// `iter` are the CLI arguments used to build the configuration. They are parsed once upfront to
+/// determine which config files to watch.
+pub fn watch<T, I, F>(iter: I, options: WatchOptions, mut callback: F) -> Result<ConfigWatcher>
+where
+    T: ConfigOpt + 'static,
+    I: IntoIterator,
+    I::Item: Into<OsString> + Clone,
+    F: FnMut(Result<T>) + Send + 'static,
+{
+    let args = iter.into_iter().map(Into::into).collect::<Vec<OsString>>();
+    let configopt = T::ConfigOptType::try_from_iter_ignore_help(&args)?;
+    // Stdin can only be read once so it can not be watched
+    let paths = configopt
+        .config_file_paths()
+        .into_iter()
+        .filter(|p| p != Path::new(STDIN_PATH))
+        .collect::<Vec<_>>();
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let thread_paths = paths.clone();
+    let thread_stop = Arc::clone(&stop);
+    let handle = thread::spawn(move || {
+        let mut last = snapshot(&thread_paths);
+        while !thread_stop.load(Ordering::SeqCst) {
+            thread::sleep(options.poll_interval);
+            let mut current = snapshot(&thread_paths);
+            if current == last {
+                continue;
+            }
+            // Wait for a burst of writes to settle before reloading
+            loop {
+                thread::sleep(options.debounce);
+                if thread_stop.load(Ordering::SeqCst) {
+                    return;
+                }
+                let next = snapshot(&thread_paths);
+                if next == current {
+                    break;
+                }
+                current = next;
+            }
+            last = current;
+            callback(T::try_from_iter_with_configopt(&args));
+        }
+    });
+
+    Ok(ConfigWatcher {
+        paths,
+        stop,
+        handle: Some(handle),
+    })
+}
+
+/// Watch the config files of `T` and send the reloaded configuration on the returned channel each
+/// time they change.
+pub fn watch_channel<T, I>(
+    iter: I,
+    options: WatchOptions,
+) -> Result<(ConfigWatcher, Receiver<Result<T>>)>
+where
+    T: ConfigOpt + Send + 'static,
+    I: IntoIterator,
+    I::Item: Into<OsString> + Clone,
+{
+    let (sender, receiver) = mpsc::channel();
+    let watcher = watch(iter, options, move |result| {
+        // The receiver hanging up is not an error, the watcher will be stopped when it is dropped
+        sender.send(result).ok();
+    })?;
+    Ok((watcher, receiver))
+}
@@ -0,0 +1,99 @@
+use configopt::{configopt_fields, ConfigOpt, Error};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use structopt::{clap::ErrorKind, StructOpt};
+use tempfile::tempdir;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize)]
+#[configopt(derive(Debug, Serialize), attrs(serde), annotate_help)]
+struct Annotated {
+    /// The port to listen on
+    #[structopt(long, default_value = "80")]
+    port: u16,
+    /// The host to bind
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    verbose: bool,
+    #[structopt(long)]
+    name: Option<String>,
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize)]
+#[configopt(derive(Debug, Serialize), attrs(serde))]
+struct NotAnnotated {
+    /// The port to listen on
+    #[structopt(long, default_value = "80")]
+    port: u16,
+}
+
+fn help_message(result: configopt::Result<impl std::fmt::Debug>) -> String {
+    match result {
+        Err(Error::Clap(e)) if e.kind == ErrorKind::HelpDisplayed => e.message,
+        result => panic!("expected help, got {:?}", result),
+    }
+}
+
+#[test]
+fn test_annotate_help() {
+    let dir = tempdir().unwrap();
+    let config_path = dir.path().join("config.toml");
+    fs::write(
+        &config_path,
+        r#"
+        port = 8080
+        host = "localhost"
+        verbose = true
+"#,
+    )
+    .unwrap();
+    let config_path = config_path.to_str().unwrap();
+
+    let help = help_message(Annotated::try_from_iter_with_configopt(&[
+        "app",
+        "--config-files",
+        config_path,
+        "--host",
+        "example.com",
+        "--help",
+    ]));
+    // The help of the app is followed by the current value of each argument and where it came from
+    assert!(help.contains("[default: 80]"));
+    let current_values = &help[help.find("\n\nCURRENT VALUES:\n").unwrap()..];
+    assert_eq!(
+        format!(
+            "\n\nCURRENT VALUES:\n    \
+             --port <port>    8080 from file '{0}'\n    \
+             --host <host>    example.com from the command line\n    \
+             --verbose        true from file '{0}'",
+            config_path
+        ),
+        current_values
+    );
+
+    // Without `--help` the values are parsed as usual
+    let s = Annotated::try_from_iter_with_configopt(&["app", "--config-files", config_path])
+        .unwrap();
+    assert_eq!(s.port, 8080);
+    assert_eq!(s.host, "localhost");
+    assert!(s.verbose);
+}
+
+#[test]
+fn test_not_annotated_help() {
+    let dir = tempdir().unwrap();
+    let config_path = dir.path().join("config.toml");
+    fs::write(&config_path, "port = 8080").unwrap();
+    let config_path = config_path.to_str().unwrap();
+
+    let help = help_message(NotAnnotated::try_from_iter_with_configopt(&[
+        "app",
+        "--config-files",
+        config_path,
+        "--help",
+    ]));
+    assert!(help.contains("[default: 80]"));
+    assert!(!help.contains("CURRENT VALUES:"));
+}
@@ -0,0 +1,119 @@
+use configopt::{ConfigFormat, ConfigOptBuilder, Error};
+use serde::Deserialize;
+use std::{env, fs};
+use structopt::clap::{App, Arg, ErrorKind, SubCommand};
+use tempfile::tempdir;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Run {
+    threads: u32,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Config {
+    host: String,
+    port: u16,
+    log_level: String,
+    verbose: bool,
+    run: Option<Run>,
+}
+
+fn app() -> App<'static, 'static> {
+    App::new("app")
+        .arg(
+            Arg::with_name("host")
+                .long("host")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("port")
+                .long("port")
+                .takes_value(true)
+                .default_value("80"),
+        )
+        .arg(
+            Arg::with_name("log-level")
+                .long("log-level")
+                .takes_value(true),
+        )
+        .arg(Arg::with_name("verbose").long("verbose"))
+        .subcommand(
+            SubCommand::with_name("run")
+                .arg(Arg::with_name("threads").long("threads").takes_value(true)),
+        )
+}
+
+#[test]
+fn test_builder() {
+    let dir = tempdir().unwrap();
+    let config_path = dir.path().join("config.toml");
+    fs::write(
+        &config_path,
+        r#"
+        host = "localhost"
+        log_level = "info"
+        verbose = true
+
+        [run]
+        threads = 2
+"#,
+    )
+    .unwrap();
+    env::set_var("CONFIGOPT_BUILDER_TEST_LOG_LEVEL", "warn");
+    env::set_var("CONFIGOPT_BUILDER_TEST_RUN__THREADS", "4");
+
+    // Later sources take precedence and the app's own default fills in the rest
+    let result = ConfigOptBuilder::new(app())
+        .file(&config_path)
+        .env("CONFIGOPT_BUILDER_TEST")
+        .try_from_iter::<Config, _>(&["app", "run"])
+        .unwrap();
+    assert_eq!(Some("localhost"), result.matches.value_of("host"));
+    assert_eq!(Some("warn"), result.matches.value_of("log-level"));
+    let run_matches = result.matches.subcommand_matches("run").unwrap();
+    assert_eq!(Some("4"), run_matches.value_of("threads"));
+    assert_eq!(
+        Config {
+            host: String::from("localhost"),
+            port: 80,
+            log_level: String::from("warn"),
+            verbose: true,
+            run: Some(Run { threads: 4 }),
+        },
+        result.config
+    );
+
+    // The command line takes precedence over every source
+    let result = ConfigOptBuilder::new(app())
+        .file(&config_path)
+        .inline(ConfigFormat::Toml, "port = 8080")
+        .try_from_iter::<Config, _>(&["app", "--host", "example.com", "--log-level", "debug"])
+        .unwrap();
+    assert_eq!(
+        Config {
+            host: String::from("example.com"),
+            port: 8080,
+            log_level: String::from("debug"),
+            verbose: true,
+            run: Some(Run { threads: 2 }),
+        },
+        result.config
+    );
+
+    // A required argument set nowhere is still an error
+    match ConfigOptBuilder::new(app()).try_from_iter::<Config, _>(&["app"]) {
+        Err(Error::Clap(e)) => assert_eq!(ErrorKind::MissingRequiredArgument, e.kind),
+        result => panic!("expected a missing argument error, got {:?}", result),
+    }
+
+    // A missing config file is reported
+    let missing = dir.path().join("missing.toml");
+    match ConfigOptBuilder::new(app())
+        .file(&missing)
+        .try_from_iter::<Config, _>(&["app"])
+    {
+        Err(e) => assert!(e.config_file_not_found()),
+        result => panic!("expected a missing config file error, got {:?}", result),
+    }
+}
@@ -0,0 +1,130 @@
+use configopt::{check_config_file, configopt_fields, ConfigOpt, Error, ParseOutcome};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use structopt::StructOpt;
+use tempfile::tempdir;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, Serialize), attrs(serde))]
+#[structopt(name = "app")]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long, default_value = "80")]
+    port: u16,
+    #[structopt(flatten)]
+    server: Server,
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, Serialize), attrs(serde))]
+#[structopt(name = "app", rename_all = "snake")]
+struct SnakeCase {
+    #[structopt(long)]
+    log_level: String,
+    name: String,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, Serialize), attrs(serde))]
+struct Server {
+    #[structopt(long)]
+    timeout: Option<u32>,
+}
+
+#[test]
+fn test_check_config_file() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    fs::write(
+        &path,
+        r#"
+        port = "NaN"
+        missing = 1
+
+        [server]
+        timeout = true
+        other = 1
+"#,
+    )
+    .unwrap();
+    let mut configopt = ConfigOptMyStruct::default();
+    let mut problems = Vec::new();
+    let value = configopt::from_toml_file(&path);
+    check_config_file(&mut configopt, &path, value, &mut problems);
+    // Every problem is reported, not only the first
+    assert_eq!(4, problems.len());
+    assert!(problems[0].starts_with("Unknown config key 'missing'"));
+    assert!(problems[1].starts_with("Invalid value for config key 'port'"));
+    assert!(problems[2].starts_with("Unknown config key 'server.other'"));
+    assert!(problems[3].starts_with("Invalid value for config key 'server.timeout'"));
+
+    // Valid values are merged and missing files are skipped
+    fs::write(&path, "host = \"localhost\"\n[server]\ntimeout = 5\n").unwrap();
+    let mut problems = Vec::new();
+    let value = configopt::from_toml_file(&path);
+    check_config_file(&mut configopt, &path, value, &mut problems);
+    let missing = dir.path().join("missing.toml");
+    let value = configopt::from_toml_file(&missing);
+    check_config_file(&mut configopt, &missing, value, &mut problems);
+    assert!(problems.is_empty());
+    assert_eq!(Some(String::from("localhost")), configopt.host);
+    assert_eq!(Some(5), configopt.server.timeout);
+}
+
+#[test]
+fn test_check_config() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    fs::write(&path, "port = \"NaN\"\n[server]\n").unwrap();
+    let path = path.to_str().unwrap();
+
+    match MyStruct::try_parse_from(&["app", "--check-config", "--config-files", path]) {
+        Err(Error::ConfigCheck(problems)) => {
+            assert_eq!(2, problems.len());
+            assert!(problems[0].starts_with("Invalid value for config key 'port'"));
+            assert_eq!("Missing required value for '--host <host>'", problems[1]);
+        }
+        result => panic!("expected config problems, got {:?}", result),
+    }
+
+    fs::write(path, "host = \"localhost\"\n[server]\n").unwrap();
+    assert_eq!(
+        ParseOutcome::ConfigChecked,
+        MyStruct::try_parse_from(&["app", "--check-config", "--config-files", path]).unwrap()
+    );
+}
+
+#[test]
+fn test_check_config_renamed_flag() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    fs::write(&path, "name = \"app\"\n").unwrap();
+    let path = path.to_str().unwrap();
+
+    // The flag is found by parsing, not by its name, so renamed arguments around it do not matter
+    match SnakeCase::try_parse_from(&["app", "--check-config", "--config-files", path]) {
+        Err(Error::ConfigCheck(problems)) => assert_eq!(
+            vec![String::from(
+                "Missing required value for '--log_level <log_level>'"
+            )],
+            problems
+        ),
+        result => panic!("expected config problems, got {:?}", result),
+    }
+
+    assert_eq!(
+        ParseOutcome::ConfigChecked,
+        SnakeCase::try_parse_from(&[
+            "app",
+            "--check-config",
+            "--log_level",
+            "info",
+            "--config-files",
+            path,
+        ])
+        .unwrap()
+    );
+}
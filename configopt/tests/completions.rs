@@ -0,0 +1,86 @@
+use configopt::{completions::Shell, configopt_fields, ConfigOpt};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path, process::Command};
+use structopt::StructOpt;
+use tempfile::tempdir;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, Serialize), attrs(serde), config_command)]
+#[structopt(name = "app")]
+struct MyStruct {
+    #[structopt(long, default_value = "80")]
+    port: u16,
+    #[structopt(flatten)]
+    server: Server,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, Serialize), attrs(serde))]
+struct Server {
+    #[structopt(long)]
+    host: Option<String>,
+}
+
+// Complete the last of `words` with the bash script, in `dir`
+fn complete(script: &str, dir: &Path, words: &[&str]) -> Vec<String> {
+    let program = format!(
+        "{}\nCOMP_WORDS=({})\nCOMP_CWORD={}\n_app\nprintf '%s\\n' \"${{COMPREPLY[@]}}\"",
+        script,
+        words
+            .iter()
+            .map(|w| format!("'{}'", w))
+            .collect::<Vec<_>>()
+            .join(" "),
+        words.len() - 1
+    );
+    let output = Command::new("bash")
+        .arg("-c")
+        .arg(program)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let mut completions = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect::<Vec<_>>();
+    completions.sort();
+    completions
+}
+
+#[test]
+fn test_bash_completions() {
+    let mut script = Vec::new();
+    MyStruct::gen_completions_to("app", Shell::Bash, &mut script).unwrap();
+    let script = String::from_utf8(script).unwrap();
+
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("app.toml"), "").unwrap();
+    fs::write(dir.path().join("notes.txt"), "").unwrap();
+    fs::create_dir(dir.path().join("configs")).unwrap();
+
+    // Config files complete to files of a config format and to directories
+    assert_eq!(
+        vec!["app.toml", "configs"],
+        complete(&script, dir.path(), &["app", "--config-files", ""])
+    );
+
+    // Other arguments are completed by clap
+    assert_eq!(
+        vec!["--port"],
+        complete(&script, dir.path(), &["app", "--po"])
+    );
+
+    // The keys of the config subcommand complete to the dotted keys
+    assert_eq!(
+        vec!["server.host"],
+        complete(&script, dir.path(), &["app", "config", "get", "s"])
+    );
+    assert_eq!(
+        vec!["port"],
+        complete(&script, dir.path(), &["app", "config", "unset", "p"])
+    );
+}
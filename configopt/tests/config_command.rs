@@ -0,0 +1,167 @@
+use configopt::{configopt_fields, ConfigCommand, ConfigOpt, Error, ParseOutcome};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use structopt::StructOpt;
+use tempfile::tempdir;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, Serialize), attrs(serde), config_command)]
+#[structopt(name = "app")]
+struct MyStruct {
+    #[structopt(long, default_value = "80")]
+    port: u16,
+    #[structopt(long)]
+    #[configopt(alias = "label")]
+    name: Option<String>,
+    #[structopt(flatten)]
+    server: Server,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, Serialize), attrs(serde))]
+struct Server {
+    #[structopt(long)]
+    host: Option<String>,
+}
+
+const CONFIG: &str = r#"# The port to listen on
+port = 80 # the default
+
+[server]
+# Where to connect
+host = "localhost"
+"#;
+
+fn run(command: ConfigCommand, path: &std::path::Path) -> configopt::Result<String> {
+    command.run::<ConfigOptMyStruct>(&[path.to_path_buf()])
+}
+
+#[test]
+fn test_config_command() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    fs::write(&path, CONFIG).unwrap();
+
+    let get = |key: &str| {
+        run(
+            ConfigCommand::Get {
+                key: String::from(key),
+            },
+            &path,
+        )
+    };
+    assert_eq!("80", get("port").unwrap());
+    assert_eq!("localhost", get("server.host").unwrap());
+    assert_eq!("", get("name").unwrap());
+
+    let set = |key: &str, value: &str| {
+        run(
+            ConfigCommand::Set {
+                key: String::from(key),
+                value: String::from(value),
+            },
+            &path,
+        )
+    };
+    set("port", "8080").unwrap();
+    set("name", "1234").unwrap();
+    set("server.host", "example.com").unwrap();
+    assert_eq!(
+        r#"# The port to listen on
+port = 8080 # the default
+name = "1234"
+
+[server]
+# Where to connect
+host = "example.com"
+"#,
+        fs::read_to_string(&path).unwrap()
+    );
+
+    // Invalid values and unknown keys leave the file unchanged
+    let before = fs::read_to_string(&path).unwrap();
+    match set("port", "NaN") {
+        Err(Error::InvalidValue(key, _)) => assert_eq!("port", key),
+        result => panic!("expected an invalid value error, got {:?}", result),
+    }
+    match set("missing", "1") {
+        Err(Error::UnknownKey(key)) => assert_eq!("missing", key),
+        result => panic!("expected an unknown key error, got {:?}", result),
+    }
+    assert_eq!(before, fs::read_to_string(&path).unwrap());
+
+    assert_eq!(
+        "name = \"1234\"\nport = 8080\nserver.host = \"example.com\"",
+        run(ConfigCommand::List, &path).unwrap()
+    );
+
+    run(
+        ConfigCommand::Unset {
+            key: String::from("name"),
+        },
+        &path,
+    )
+    .unwrap();
+    assert!(!fs::read_to_string(&path).unwrap().contains("name"));
+
+    // A file that does not exist yet is created
+    let new_path = dir.path().join("new.toml");
+    run(
+        ConfigCommand::Set {
+            key: String::from("server.host"),
+            value: String::from("localhost"),
+        },
+        &new_path,
+    )
+    .unwrap();
+    assert_eq!(
+        "[server]\nhost = \"localhost\"\n",
+        fs::read_to_string(&new_path).unwrap()
+    );
+
+    // Keys are read the same as when parsing, so old key names are still found
+    fs::write(&path, "label = \"old\"\n\n[server]\n").unwrap();
+    assert_eq!("old", get("name").unwrap());
+    assert_eq!("name = \"old\"", run(ConfigCommand::List, &path).unwrap());
+}
+
+#[test]
+fn test_config_subcommand() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    fs::write(&path, CONFIG).unwrap();
+    let path = path.to_str().unwrap();
+
+    assert_eq!(
+        ParseOutcome::ConfigCommand(String::from("80")),
+        MyStruct::try_parse_from(&["app", "--config-files", path, "config", "get", "port"])
+            .unwrap()
+    );
+    match MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--config-files",
+        path,
+        "config",
+        "set",
+        "port",
+        "8080",
+    ]) {
+        Err(Error::ConfigCommand(output)) => assert_eq!("", output),
+        result => panic!("expected the config command to run, got {:?}", result),
+    }
+    assert_eq!(
+        MyStruct {
+            port: 8080,
+            name: None,
+            server: Server {
+                host: Some(String::from("localhost")),
+            },
+            config_files: vec![path.into()],
+            generate_config: false,
+            check_config: false,
+            explain_config: false,
+        },
+        MyStruct::try_from_iter_with_configopt(&["app", "--config-files", path]).unwrap()
+    );
+}
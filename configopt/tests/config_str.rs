@@ -0,0 +1,99 @@
+use configopt::{configopt_fields, ConfigFormat, ConfigOpt};
+use serde::Deserialize;
+use std::{
+    convert::TryFrom,
+    env,
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+};
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, PartialEq), attrs(serde))]
+#[serde(deny_unknown_fields)]
+struct MyStruct {
+    #[structopt(long)]
+    value: String,
+    #[structopt(long)]
+    number: Option<u32>,
+}
+
+#[test]
+fn test_from_config_str() {
+    let c = ConfigOptMyStruct::from_config_str(
+        ConfigFormat::Toml,
+        r#"
+        value = "from_str"
+        number = 5
+"#,
+    )
+    .unwrap();
+    assert_eq!(
+        ConfigOptMyStruct {
+            value: Some(String::from("from_str")),
+            number: Some(5),
+        },
+        c
+    );
+
+    let c = ConfigOptMyStruct::from_config_str(ConfigFormat::Toml, "").unwrap();
+    assert_eq!(ConfigOptMyStruct::default(), c);
+
+    assert!(ConfigOptMyStruct::from_config_str(ConfigFormat::Toml, "unknown = 1").is_err());
+    assert!(ConfigOptMyStruct::from_config_str(ConfigFormat::Toml, "number = \"NaN\"").is_err());
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, PartialEq), attrs(serde), interpolate_env)]
+#[serde(deny_unknown_fields)]
+struct Renamed {
+    #[structopt(long)]
+    #[configopt(alias = "old_value")]
+    value: String,
+}
+
+#[test]
+fn test_from_config_str_reads_like_a_config_file() {
+    env::set_var("CONFIGOPT_CONFIG_STR_VALUE", "from_env");
+    let c = ConfigOptRenamed::from_config_str(
+        ConfigFormat::Toml,
+        "old_value = \"${CONFIGOPT_CONFIG_STR_VALUE}\"",
+    )
+    .unwrap();
+    assert_eq!(Some(String::from("from_env")), c.value);
+}
+
+// Run in a child process with the config piped to stdin, the parent only spawns the child
+#[test]
+fn test_stdin_is_read_once() {
+    if env::var_os("CONFIGOPT_TEST_STDIN").is_none() {
+        let mut child = Command::new(env::current_exe().unwrap())
+            .args(&["--exact", "test_stdin_is_read_once", "--nocapture"])
+            .env("CONFIGOPT_TEST_STDIN", "1")
+            .stdin(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(b"value = \"from_stdin\"")
+            .unwrap();
+        assert!(child.wait().unwrap().success());
+        return;
+    }
+
+    let r = Renamed::try_from_iter_with_configopt(&["app", "--config-files", "-", "-"]).unwrap();
+    assert_eq!("from_stdin", r.value);
+    // Later reads return the same contents instead of an empty document
+    let c = ConfigOptRenamed::try_from(Path::new("-")).unwrap();
+    assert_eq!(Some(String::from("from_stdin")), c.value);
+}
+
+#[test]
+fn test_config_format_from_str() {
+    assert_eq!(ConfigFormat::Toml, "toml".parse().unwrap());
+    assert!("yaml".parse::<ConfigFormat>().is_err());
+}
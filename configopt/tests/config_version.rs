@@ -0,0 +1,131 @@
+use configopt::{configopt_fields, ConfigOpt, ConfigOptType, Error};
+use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, fs};
+use structopt::StructOpt;
+use tempfile::tempdir;
+use toml::{value::Table, Value};
+
+// Version 2 renamed `listen_port` to `port`
+fn v1_to_v2(table: &mut Table) -> Result<(), String> {
+    if let Some(port) = table.remove("listen_port") {
+        table.insert(String::from("port"), port);
+    }
+    Ok(())
+}
+
+// Version 3 moved `host` into the `server` table
+fn v2_to_v3(table: &mut Table) -> Result<(), String> {
+    let mut server = Table::new();
+    if let Some(host) = table.remove("host") {
+        server.insert(String::from("host"), host);
+    }
+    table.insert(String::from("server"), Value::Table(server));
+    Ok(())
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, Serialize), attrs(serde), version = 3)]
+#[configopt(upgrade(1 => v1_to_v2, 2 => v2_to_v3))]
+#[structopt(name = "app")]
+struct MyStruct {
+    #[structopt(long)]
+    port: Option<u16>,
+    #[structopt(flatten)]
+    server: Server,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, Serialize), attrs(serde))]
+struct Server {
+    #[structopt(long)]
+    host: Option<String>,
+}
+
+#[test]
+fn test_config_version() {
+    assert_eq!(Some(3), ConfigOptMyStruct::config_version());
+    assert_eq!(None, ConfigOptServer::config_version());
+
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    let read = |contents: &str| {
+        fs::write(&path, contents).unwrap();
+        ConfigOptMyStruct::try_from(path.as_path())
+    };
+
+    // Older files are upgraded one version at a time
+    let configopt = read("config_version = 1\nlisten_port = 8080\nhost = \"localhost\"\n").unwrap();
+    assert_eq!(Some(8080), configopt.port);
+    assert_eq!(Some(String::from("localhost")), configopt.server.host);
+    let configopt = read("config_version = 2\nport = 8080\nhost = \"localhost\"\n").unwrap();
+    assert_eq!(Some(8080), configopt.port);
+    assert_eq!(Some(String::from("localhost")), configopt.server.host);
+
+    // The current version is read as is and a file without a version is version 1
+    let configopt = read("config_version = 3\nport = 8080\n[server]\n").unwrap();
+    assert_eq!(Some(8080), configopt.port);
+    let configopt = read("listen_port = 8080\n").unwrap();
+    assert_eq!(Some(8080), configopt.port);
+
+    match read("config_version = 4\n[server]\n") {
+        Err(Error::UpgradeConfig(_, e)) => assert_eq!(
+            "'config_version' 4 is newer than the supported version 3",
+            e
+        ),
+        result => panic!("expected an upgrade error, got {:?}", result),
+    }
+}
+
+#[test]
+fn test_upgrade_config_file() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    fs::write(
+        &path,
+        "config_version = 1\nlisten_port = 8080\nhost = \"localhost\"\n",
+    )
+    .unwrap();
+
+    assert_eq!(
+        Some(1),
+        ConfigOptMyStruct::upgrade_config_file(&path).unwrap()
+    );
+    assert_eq!(
+        "config_version = 3\nport = 8080\n\n[server]\nhost = \"localhost\"\n",
+        fs::read_to_string(&path).unwrap()
+    );
+
+    // An upgraded file is left alone
+    assert_eq!(None, ConfigOptMyStruct::upgrade_config_file(&path).unwrap());
+    let configopt = ConfigOptMyStruct::try_from(path.as_path()).unwrap();
+    assert_eq!(Some(8080), configopt.port);
+    assert_eq!(Some(String::from("localhost")), configopt.server.host);
+
+    // The comments and formatting of unchanged keys are kept
+    fs::write(
+        &path,
+        "# The app\nconfig_version = 2\n\n# The port\nport   = 8080 # not the default\nhost = \"localhost\"\n",
+    )
+    .unwrap();
+    assert_eq!(
+        Some(2),
+        ConfigOptMyStruct::upgrade_config_file(&path).unwrap()
+    );
+    assert_eq!(
+        "# The app\nconfig_version = 3\n\n# The port\nport   = 8080 # not the default\n\n[server]\nhost = \"localhost\"\n",
+        fs::read_to_string(&path).unwrap()
+    );
+
+    // A file without a version is upgraded from version 1. The comments of a key moved by an
+    // upgrade are removed with it.
+    fs::write(&path, "# The port\nlisten_port = 8080\n").unwrap();
+    assert_eq!(
+        Some(1),
+        ConfigOptMyStruct::upgrade_config_file(&path).unwrap()
+    );
+    assert_eq!(
+        "config_version = 3\nport = 8080\nserver = {}\n",
+        fs::read_to_string(&path).unwrap()
+    );
+}
@@ -0,0 +1,186 @@
+use configopt::{
+    configopt_fields,
+    constraints::{Constraint, ConstraintArg},
+    ConfigOpt, ConfigOptType, Error,
+};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use structopt::{clap::ErrorKind, StructOpt};
+use tempfile::tempdir;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, Serialize), attrs(serde))]
+#[structopt(name = "app")]
+struct MyStruct {
+    #[structopt(long, requires = "key")]
+    cert: Option<String>,
+    #[structopt(long)]
+    key: Option<String>,
+    #[structopt(long, conflicts_with_all = &["cert", "key"])]
+    insecure: bool,
+    #[structopt(long, default_value = "tcp")]
+    protocol: String,
+    #[structopt(flatten)]
+    server: Server,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, Serialize), attrs(serde))]
+struct Server {
+    #[structopt(long, required_if("protocol", "unix"))]
+    socket: Option<String>,
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, Serialize), attrs(serde))]
+#[structopt(name = "app")]
+struct WithRequired {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long, requires = "key")]
+    cert: Option<String>,
+    #[structopt(long)]
+    key: Option<String>,
+}
+
+#[test]
+fn test_constraint_args() {
+    let configopt = ConfigOptMyStruct::default();
+    let mut group = Vec::new();
+    let mut groups = Vec::new();
+    configopt.constraint_args_with_prefix(&[], &mut group, &mut groups);
+    assert!(groups.is_empty());
+    assert_eq!(
+        vec![
+            ConstraintArg {
+                name: "cert",
+                usage: "--cert <cert>",
+                key: String::from("cert"),
+                constraints: vec![Constraint::Requires(vec!["key"])],
+            },
+            ConstraintArg {
+                name: "key",
+                usage: "--key <key>",
+                key: String::from("key"),
+                constraints: Vec::new(),
+            },
+            ConstraintArg {
+                name: "insecure",
+                usage: "--insecure",
+                key: String::from("insecure"),
+                constraints: vec![Constraint::ConflictsWith(vec!["cert", "key"])],
+            },
+            ConstraintArg {
+                name: "protocol",
+                usage: "--protocol <protocol>",
+                key: String::from("protocol"),
+                constraints: Vec::new(),
+            },
+            ConstraintArg {
+                name: "socket",
+                usage: "--socket <socket>",
+                key: String::from("server.socket"),
+                constraints: vec![Constraint::RequiredIfAny(vec![("protocol", "unix")])],
+            },
+        ],
+        group
+    );
+}
+
+#[test]
+fn test_constraints_with_config_files() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    let path_str = path.to_str().unwrap();
+    let parse = |args: &[&str]| {
+        let mut iter = vec!["app", "--config-files", path_str];
+        iter.extend_from_slice(args);
+        MyStruct::try_from_iter_with_configopt(iter)
+    };
+
+    // The required argument is set in the config file
+    fs::write(&path, "key = \"key.pem\"\n[server]\n").unwrap();
+    let s = parse(&["--cert", "cert.pem"]).unwrap();
+    assert_eq!(Some(String::from("key.pem")), s.key);
+
+    // A conflict between the command line and a config file
+    match parse(&["--insecure"]) {
+        Err(Error::Constraints(problems)) => assert_eq!(
+            vec![format!(
+                "The argument '--insecure' cannot be used with '--key <key>' (config key 'key' \
+                 from file '{}')",
+                path.display()
+            )],
+            problems
+        ),
+        result => panic!("expected a constraint error, got {:?}", result),
+    }
+
+    // Required by a value from a config file
+    fs::write(
+        &path,
+        "cert = \"cert.pem\"\nprotocol = \"unix\"\n[server]\n",
+    )
+    .unwrap();
+    match parse(&[]) {
+        Err(Error::Constraints(problems)) => assert_eq!(
+            vec![
+                format!(
+                    "Missing required value for '--key <key>' which is required by '--cert \
+                     <cert>' (config key 'cert' from file '{}')",
+                    path.display()
+                ),
+                format!(
+                    "Missing required value for '--socket <socket>' which is required when \
+                     '--protocol <protocol>' (config key 'protocol' from file '{}') is 'unix'",
+                    path.display()
+                ),
+            ],
+            problems
+        ),
+        result => panic!("expected a constraint error, got {:?}", result),
+    }
+
+    // A value from a config file overrides the default value
+    fs::write(
+        &path,
+        "protocol = \"unix\"\n[server]\nsocket = \"app.sock\"\n",
+    )
+    .unwrap();
+    assert_eq!("unix", parse(&[]).unwrap().protocol);
+
+    // Default values do not count as being set
+    fs::write(&path, "[server]\n").unwrap();
+    assert!(parse(&["--insecure"]).is_ok());
+}
+
+#[test]
+fn test_missing_required_arguments() {
+    // Without a config file clap reports the missing argument
+    match WithRequired::try_from_iter_with_configopt(&["app", "--cert", "cert.pem"]) {
+        Err(Error::Clap(e)) => assert_eq!(ErrorKind::MissingRequiredArgument, e.kind),
+        result => panic!("expected a missing argument error, got {:?}", result),
+    }
+
+    // With a config file every layer is checked
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    fs::write(&path, "cert = \"cert.pem\"\n").unwrap();
+    let path_str = path.to_str().unwrap();
+    match WithRequired::try_from_iter_with_configopt(&["app", "--config-files", path_str]) {
+        Err(Error::Constraints(problems)) => assert_eq!(
+            vec![
+                String::from("Missing required value for '--host <host>'"),
+                format!(
+                    "Missing required value for '--key <key>' which is required by '--cert \
+                     <cert>' (config key 'cert' from file '{}')",
+                    path.display()
+                ),
+            ],
+            problems
+        ),
+        result => panic!("expected a constraint error, got {:?}", result),
+    }
+}
@@ -0,0 +1,114 @@
+use configopt::{configopt_fields, take_deprecation_warnings, ConfigOpt, ConfigOptType};
+use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, fs};
+use structopt::StructOpt;
+use tempfile::tempdir;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, Serialize), attrs(serde))]
+#[structopt(name = "app")]
+struct MyStruct {
+    #[structopt(long)]
+    #[configopt(alias = "listen_port", deprecated = "use port")]
+    port: Option<u16>,
+    #[structopt(long)]
+    #[configopt(deprecated = "it has no effect")]
+    legacy: Option<String>,
+    #[structopt(flatten)]
+    #[configopt(alias = "backend")]
+    server: Server,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, Serialize), attrs(serde))]
+struct Server {
+    #[structopt(long)]
+    #[configopt(alias = "address")]
+    host: Option<String>,
+}
+
+const OLD_CONFIG: &str = r#"# The port to listen on
+listen_port = 8080 # not the default
+legacy = "yes"
+
+[backend]
+# Where to connect
+address = "localhost"
+"#;
+
+#[test]
+fn test_deprecated_keys() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    fs::write(&path, OLD_CONFIG).unwrap();
+
+    // Old keys are still read, with a warning naming the key as written and the file
+    let configopt = ConfigOptMyStruct::try_from(path.as_path()).unwrap();
+    assert_eq!(Some(8080), configopt.port);
+    assert_eq!(Some(String::from("localhost")), configopt.server.host);
+    let warnings = take_deprecation_warnings();
+    let in_file = format!(" in file '{}'", path.display());
+    assert_eq!(
+        vec![
+            format!(
+                "Config key 'listen_port' was renamed to 'port', use port{}",
+                in_file
+            ),
+            format!(
+                "Config key 'legacy' is deprecated, it has no effect{}",
+                in_file
+            ),
+            format!("Config key 'backend' was renamed to 'server'{}", in_file),
+            format!(
+                "Config key 'backend.address' was renamed to 'server.host'{}",
+                in_file
+            ),
+        ],
+        warnings
+    );
+
+    // The current name takes precedence
+    fs::write(&path, "port = 1\nlisten_port = 2\n[server]\n").unwrap();
+    let configopt = ConfigOptMyStruct::try_from(path.as_path()).unwrap();
+    assert_eq!(Some(1), configopt.port);
+
+    // Old keys work with dotted keys
+    let mut configopt = ConfigOptMyStruct::default();
+    configopt.set("backend.address", "example.com").unwrap();
+    assert_eq!(Some(String::from("example.com")), configopt.server.host);
+    assert_eq!(
+        Some(String::from("example.com")),
+        configopt.get::<String>("server.host").unwrap()
+    );
+    assert_eq!(
+        Some(String::from("example.com")),
+        configopt.get::<String>("backend.address").unwrap()
+    );
+}
+
+#[test]
+fn test_migrate_config_file() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    fs::write(&path, OLD_CONFIG).unwrap();
+
+    let deprecated = ConfigOptMyStruct::migrate_config_file(&path).unwrap();
+    assert_eq!(4, deprecated.len());
+    assert_eq!(
+        r#"# The port to listen on
+port = 8080 # not the default
+legacy = "yes"
+
+[server]
+# Where to connect
+host = "localhost"
+"#,
+        fs::read_to_string(&path).unwrap()
+    );
+
+    // A migrated file has nothing left to migrate
+    let deprecated = ConfigOptMyStruct::migrate_config_file(&path).unwrap();
+    assert_eq!(1, deprecated.len());
+    assert_eq!(None, deprecated[0].renamed_to);
+}
@@ -0,0 +1,161 @@
+use configopt::{ConfigChange, ConfigOpt};
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+use toml::Value;
+
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, Serialize, PartialEq)]
+#[configopt(derive(Debug, PartialEq, Serialize), attrs(serde))]
+struct MyStruct {
+    #[structopt(long)]
+    port: u16,
+    #[structopt(long)]
+    host: Option<String>,
+    #[structopt(flatten)]
+    #[serde(flatten)]
+    flat: FlatStruct,
+    #[structopt(subcommand)]
+    #[serde(skip)]
+    cmd: MyEnum,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, Serialize, PartialEq)]
+#[configopt(derive(Debug, PartialEq, Serialize), attrs(serde))]
+struct FlatStruct {
+    #[structopt(long)]
+    flat_names: Vec<String>,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, Serialize, PartialEq)]
+#[configopt(derive(Debug, PartialEq, Serialize), attrs(serde))]
+enum MyEnum {
+    Cmd1,
+    Cmd2 {
+        #[structopt(long)]
+        field: Option<String>,
+    },
+    Cmd3(AnotherStruct),
+}
+
+impl Default for MyEnum {
+    fn default() -> Self {
+        Self::Cmd1
+    }
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, Serialize, PartialEq)]
+#[configopt(derive(Debug, PartialEq, Serialize), attrs(serde))]
+struct AnotherStruct {
+    #[structopt(long)]
+    level: u32,
+}
+
+fn my_struct(port: u16, cmd: MyEnum) -> MyStruct {
+    MyStruct {
+        port,
+        host: None,
+        flat: FlatStruct {
+            flat_names: vec![String::from("a")],
+        },
+        cmd,
+    }
+}
+
+#[test]
+fn test_diff_configopt_type() {
+    let old = ConfigOptMyStruct {
+        port: Some(8080),
+        host: Some(String::from("localhost")),
+        ..ConfigOptMyStruct::default()
+    };
+    let new = ConfigOptMyStruct {
+        port: Some(9090),
+        flat: ConfigOptFlatStruct {
+            flat_names: Some(vec![String::from("a")]),
+        },
+        cmd: Some(ConfigOptMyEnum::Cmd3(ConfigOptAnotherStruct { level: Some(1) })),
+        ..ConfigOptMyStruct::default()
+    };
+    let diff = old.diff(&new);
+    assert_eq!(
+        &[
+            ConfigChange {
+                path: String::from("port"),
+                old: Some(Value::Integer(8080)),
+                new: Some(Value::Integer(9090)),
+            },
+            ConfigChange {
+                path: String::from("host"),
+                old: Some(Value::String(String::from("localhost"))),
+                new: None,
+            },
+            ConfigChange {
+                path: String::from("flat_names"),
+                old: None,
+                new: Some(Value::Array(vec![Value::String(String::from("a"))])),
+            },
+            ConfigChange {
+                path: String::from("cmd"),
+                old: None,
+                new: Some(Value::String(String::from("cmd3"))),
+            },
+        ],
+        diff.changes()
+    );
+    assert_eq!(
+        "port: 8080 -> 9090\nhost: \"localhost\" -> <unset>\nflat_names: <unset> -> [\"a\"]\ncmd: <unset> -> \"cmd3\"\n",
+        diff.to_string()
+    );
+    assert!(old.diff(&old).is_empty());
+}
+
+#[test]
+fn test_diff_resolved_type() {
+    let old = my_struct(8080, MyEnum::Cmd3(AnotherStruct { level: 1 }));
+    let new = my_struct(8080, MyEnum::Cmd3(AnotherStruct { level: 2 }));
+    assert_eq!(
+        &[ConfigChange {
+            path: String::from("cmd3.level"),
+            old: Some(Value::Integer(1)),
+            new: Some(Value::Integer(2)),
+        }],
+        old.diff(&new).changes()
+    );
+
+    let old = my_struct(8080, MyEnum::Cmd2 { field: None });
+    let new = my_struct(
+        8080,
+        MyEnum::Cmd2 {
+            field: Some(String::from("value")),
+        },
+    );
+    assert_eq!(
+        &[ConfigChange {
+            path: String::from("cmd2.field"),
+            old: None,
+            new: Some(Value::String(String::from("value"))),
+        }],
+        old.diff(&new).changes()
+    );
+
+    let old = my_struct(8080, MyEnum::Cmd1);
+    let new = my_struct(8080, MyEnum::Cmd2 { field: None });
+    assert_eq!(
+        &[ConfigChange {
+            path: String::from("cmd"),
+            old: Some(Value::String(String::from("cmd1"))),
+            new: Some(Value::String(String::from("cmd2"))),
+        }],
+        old.diff(&new).changes()
+    );
+    assert!(old.diff(&old).is_empty());
+}
+
+#[test]
+fn test_diff_serialize() {
+    let old = my_struct(8080, MyEnum::Cmd1);
+    let new = my_struct(9090, MyEnum::Cmd1);
+    assert_eq!(
+        r#"[{"path":"port","old":8080,"new":9090}]"#,
+        serde_json::to_string(&old.diff(&new)).unwrap()
+    );
+}
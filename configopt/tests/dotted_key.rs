@@ -0,0 +1,133 @@
+use configopt::{configopt_fields, ConfigOpt, ConfigOptType, Error};
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize)]
+#[configopt(derive(Debug, PartialEq, Serialize), attrs(serde))]
+struct MyStruct {
+    #[structopt(long)]
+    port: u16,
+    #[structopt(long)]
+    verbose: bool,
+    #[structopt(flatten)]
+    server: Server,
+    #[structopt(subcommand)]
+    #[serde(skip)]
+    cmd: MyEnum,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize)]
+#[configopt(derive(Debug, PartialEq, Serialize), attrs(serde))]
+struct Server {
+    #[structopt(long)]
+    host: Option<String>,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug, PartialEq, Serialize), attrs(serde))]
+enum MyEnum {
+    Cmd1,
+    Cmd2 {
+        #[structopt(long)]
+        field_1: String,
+    },
+    Cmd3(AnotherStruct),
+}
+
+impl Default for MyEnum {
+    fn default() -> Self {
+        Self::Cmd1
+    }
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize)]
+#[configopt(derive(Debug, PartialEq, Serialize), attrs(serde))]
+struct AnotherStruct {
+    #[structopt(long)]
+    field_a: String,
+    #[structopt(flatten)]
+    #[serde(flatten)]
+    flat_struct: FlatStruct,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize)]
+#[configopt(derive(Debug, PartialEq, Serialize), attrs(serde))]
+struct FlatStruct {
+    #[structopt(long)]
+    flat_optional: Option<u32>,
+}
+
+#[test]
+fn test_get() {
+    let c = ConfigOptMyStruct::from_iter_safe(&[
+        "app",
+        "--port",
+        "80",
+        "--host",
+        "localhost",
+        "cmd3",
+        "--flat-optional",
+        "6",
+    ])
+    .unwrap();
+    assert_eq!(Some(toml::Value::Integer(80)), c.get_value("port").unwrap());
+    assert_eq!(Some(80), c.get::<u16>("port").unwrap());
+    assert_eq!(None, c.get::<bool>("verbose").unwrap());
+    assert_eq!(
+        Some(String::from("localhost")),
+        c.get::<String>("server.host").unwrap()
+    );
+    assert_eq!(Some(6), c.get::<u32>("cmd3.flat_optional").unwrap());
+    assert_eq!(None, c.get::<String>("cmd3.field_a").unwrap());
+    // Keys of a subcommand that is not set are known but unset
+    assert_eq!(None, c.get::<String>("cmd2.field_1").unwrap());
+
+    match c.get_value("cmd3.missing") {
+        Err(Error::UnknownKey(key)) => assert_eq!("cmd3.missing", key),
+        result => panic!("expected an unknown key error, got {:?}", result),
+    }
+    match c.get::<bool>("port") {
+        Err(Error::InvalidValue(key, _)) => assert_eq!("port", key),
+        result => panic!("expected an invalid value error, got {:?}", result),
+    }
+}
+
+#[test]
+fn test_set() {
+    let mut c = ConfigOptMyStruct::default();
+    c.set("port", 8080).unwrap();
+    c.set("verbose", true).unwrap();
+    c.set("server.host", "example.com").unwrap();
+    assert_eq!(Some(8080), c.port);
+    assert_eq!(Some(true), *c.verbose);
+    assert_eq!(Some(String::from("example.com")), c.server.host);
+
+    // Setting a key of a subcommand selects that subcommand
+    c.set("cmd3.flat_optional", 7).unwrap();
+    assert_eq!(Some(7), c.get::<u32>("cmd3.flat_optional").unwrap());
+    c.set("cmd3.field_a", "a").unwrap();
+    assert_eq!(
+        Some(String::from("a")),
+        c.get::<String>("cmd3.field_a").unwrap()
+    );
+    c.set("cmd2.field_1", "one").unwrap();
+    assert_eq!(
+        Some(ConfigOptMyEnum::Cmd2 {
+            field_1: Some(String::from("one"))
+        }),
+        c.cmd
+    );
+
+    match c.set("port", "NaN") {
+        Err(Error::InvalidValue(key, _)) => assert_eq!("port", key),
+        result => panic!("expected an invalid value error, got {:?}", result),
+    }
+    assert_eq!(Some(8080), c.port);
+    match c.set("cmd1.missing", 1) {
+        Err(Error::UnknownKey(key)) => assert_eq!("cmd1.missing", key),
+        result => panic!("expected an unknown key error, got {:?}", result),
+    }
+    assert_eq!(Some("cmd2"), c.cmd.as_ref().and_then(|c| c.variant_name()));
+}
@@ -0,0 +1,122 @@
+use configopt::{ConfigOpt, Error};
+use serde::Deserialize;
+use std::{convert::TryFrom, io::Write, path::PathBuf};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, PartialEq), attrs(serde))]
+struct MyStruct {
+    #[structopt(long)]
+    #[configopt(interpolate_env)]
+    path: PathBuf,
+    #[structopt(long)]
+    verbatim: Option<String>,
+    #[structopt(flatten)]
+    #[serde(flatten)]
+    flat: FlatStruct,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, PartialEq), attrs(serde), interpolate_env)]
+#[serde(deny_unknown_fields)]
+struct FlatStruct {
+    #[structopt(long)]
+    flat_names: Vec<String>,
+    #[structopt(long)]
+    flat_port: Option<u16>,
+}
+
+fn config_file(contents: &str) -> NamedTempFile {
+    let mut config_file = NamedTempFile::new().unwrap();
+    write!(config_file, "{}", contents).unwrap();
+    config_file
+}
+
+#[test]
+fn test_env_interpolation() {
+    std::env::set_var("CONFIGOPT_TEST_DATA_DIR", "/var/lib/app");
+    std::env::set_var("CONFIGOPT_TEST_NAME", "name");
+    let file = config_file(
+        r#"
+        path = "${CONFIGOPT_TEST_DATA_DIR}/cache"
+        verbatim = "${CONFIGOPT_TEST_DATA_DIR}"
+        flat_names = ["${CONFIGOPT_TEST_NAME}", "${CONFIGOPT_TEST_MISSING:-fallback}", "$${CONFIGOPT_TEST_NAME}"]
+        flat_port = 8080
+"#,
+    );
+    let c = ConfigOptMyStruct::try_from(file.path()).unwrap();
+    assert_eq!(Some(PathBuf::from("/var/lib/app/cache")), c.path);
+    assert_eq!(Some(String::from("${CONFIGOPT_TEST_DATA_DIR}")), c.verbatim);
+    assert_eq!(
+        Some(vec![
+            String::from("name"),
+            String::from("fallback"),
+            String::from("${CONFIGOPT_TEST_NAME}")
+        ]),
+        c.flat.flat_names
+    );
+    assert_eq!(Some(8080), c.flat.flat_port);
+}
+
+#[test]
+fn test_env_interpolation_unknown_variable() {
+    let file = config_file(
+        r#"
+        path = "${CONFIGOPT_TEST_UNKNOWN}/cache"
+"#,
+    );
+    match ConfigOptMyStruct::try_from(file.path()) {
+        Err(Error::Interpolation(path, key, _)) => {
+            assert_eq!(file.path(), path);
+            assert_eq!("path", key);
+        }
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, PartialEq), attrs(serde), interpolate_env)]
+struct WithSubcommand {
+    #[structopt(subcommand)]
+    cmd: Command,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, PartialEq), attrs(serde))]
+enum Command {
+    Serve {
+        #[structopt(long)]
+        root: Option<String>,
+    },
+    Flat(FlatStruct),
+}
+
+#[test]
+fn test_env_interpolation_in_subcommand() {
+    std::env::set_var("CONFIGOPT_TEST_ROOT", "/srv");
+    let file = config_file(
+        r#"
+        [serve]
+        root = "${CONFIGOPT_TEST_ROOT}/www"
+"#,
+    );
+    let c = ConfigOptWithSubcommand::try_from(file.path()).unwrap();
+    assert_eq!(
+        Some(ConfigOptCommand::Serve {
+            root: Some(String::from("/srv/www"))
+        }),
+        c.cmd
+    );
+
+    let file = config_file(
+        r#"
+        [flat]
+        flat_names = ["${CONFIGOPT_TEST_UNKNOWN}"]
+"#,
+    );
+    match ConfigOptWithSubcommand::try_from(file.path()) {
+        Err(Error::Interpolation(_, key, _)) => assert_eq!("flat.flat_names[0]", key),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
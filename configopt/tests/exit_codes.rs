@@ -0,0 +1,117 @@
+use configopt::{configopt_fields, ConfigOpt, Error, ErrorKind, ExitCodes};
+use serde::Deserialize;
+use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+use structopt::{
+    clap::{Error as ClapError, ErrorKind as ClapErrorKind},
+    StructOpt,
+};
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize)]
+#[configopt(attrs(serde), default_config_file("/does/not/exist/config.toml"))]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+}
+
+fn config_file_error(kind: IoErrorKind) -> Error {
+    Error::ConfigFile("config.toml".into(), IoError::new(kind, "err"))
+}
+
+fn clap_error(kind: ClapErrorKind) -> Error {
+    Error::Clap(ClapError::with_description("err", kind))
+}
+
+#[test]
+fn test_error_kind() {
+    assert_eq!(
+        ErrorKind::Output,
+        Error::ConfigGenerated(String::new()).kind()
+    );
+    assert_eq!(
+        ErrorKind::Output,
+        clap_error(ClapErrorKind::HelpDisplayed).kind()
+    );
+    assert_eq!(
+        ErrorKind::Output,
+        clap_error(ClapErrorKind::VersionDisplayed).kind()
+    );
+    assert_eq!(
+        ErrorKind::Usage,
+        clap_error(ClapErrorKind::UnknownArgument).kind()
+    );
+    assert_eq!(
+        ErrorKind::Usage,
+        clap_error(ClapErrorKind::MissingRequiredArgument).kind()
+    );
+    assert_eq!(ErrorKind::Io, clap_error(ClapErrorKind::Io).kind());
+    assert_eq!(
+        ErrorKind::ConfigFileMissing,
+        config_file_error(IoErrorKind::NotFound).kind()
+    );
+    assert_eq!(
+        ErrorKind::PermissionDenied,
+        config_file_error(IoErrorKind::PermissionDenied).kind()
+    );
+    assert_eq!(
+        ErrorKind::Config,
+        config_file_error(IoErrorKind::InvalidData).kind()
+    );
+    assert_eq!(
+        ErrorKind::Config,
+        Error::ConfigStr(IoError::new(IoErrorKind::InvalidData, "err")).kind()
+    );
+    assert_eq!(
+        ErrorKind::Config,
+        Error::Interpolation("config.toml".into(), String::from("key"), String::new()).kind()
+    );
+}
+
+#[test]
+fn test_exit_codes() {
+    let codes = ExitCodes::default();
+    assert_eq!(64, codes.code(ErrorKind::Usage));
+    assert_eq!(66, codes.code(ErrorKind::ConfigFileMissing));
+    assert_eq!(78, codes.code(ErrorKind::Config));
+    assert_eq!(77, codes.code(ErrorKind::PermissionDenied));
+    assert_eq!(0, codes.code(ErrorKind::Output));
+
+    let codes = ExitCodes {
+        usage: 2,
+        ..ExitCodes::default()
+    };
+    assert_eq!(2, codes.code(ErrorKind::Usage));
+    assert_eq!(78, codes.code(ErrorKind::Config));
+}
+
+#[test]
+fn test_missing_config_file_exit_code() {
+    let codes = ExitCodes::default();
+
+    // A missing config file given on the command line is an error
+    let e = MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--config-files",
+        "/does/not/exist/other.toml",
+        "--host",
+        "localhost",
+    ])
+    .unwrap_err();
+    assert_eq!(ErrorKind::ConfigFileMissing, e.kind());
+    assert_eq!(66, codes.code(e.kind()));
+
+    // Even when a required argument is also missing
+    let e = MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--config-files",
+        "/does/not/exist/other.toml",
+    ])
+    .unwrap_err();
+    assert_eq!(66, codes.code(e.kind()));
+
+    // A missing default config file is skipped
+    let s = MyStruct::try_from_iter_with_configopt(&["app", "--host", "localhost"]).unwrap();
+    assert_eq!("localhost", s.host);
+    let e = MyStruct::try_from_iter_with_configopt(&["app"]).unwrap_err();
+    assert_eq!(64, codes.code(e.kind()));
+}
@@ -0,0 +1,138 @@
+use configopt::{
+    check_config_file, configopt_fields, ConfigOpt, ConfigOptArgToOsString, ConfigOptBool,
+    ConfigOptType, Error,
+};
+use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, fs};
+use structopt::StructOpt;
+use tempfile::tempdir;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, Serialize), attrs(serde))]
+#[structopt(name = "app")]
+struct MyStruct {
+    #[structopt(long)]
+    port: Option<u16>,
+    /// Print more output
+    #[structopt(long)]
+    #[configopt(cli_only)]
+    verbose: bool,
+    /// The API token
+    #[structopt(skip)]
+    #[configopt(file_only)]
+    token: Option<String>,
+    #[structopt(skip)]
+    #[serde(skip)]
+    #[configopt(skip)]
+    cache: Vec<String>,
+}
+
+const CONFIG: &str = r#"port = 8080
+verbose = true
+token = "secret"
+cache = ["a"]
+"#;
+
+#[test]
+fn test_field_sources_in_config_files() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    fs::write(&path, CONFIG).unwrap();
+
+    // Only `port` and `token` are read from the file
+    let mut configopt = ConfigOptMyStruct::try_from(path.as_path()).unwrap();
+    assert_eq!(Some(8080), configopt.port);
+    assert_eq!(ConfigOptBool::default(), configopt.verbose);
+    assert_eq!(Some(String::from("secret")), configopt.token);
+    assert_eq!(None, configopt.cache);
+
+    // The other keys are unknown to `check_config_file` and `set`
+    let mut problems = Vec::new();
+    check_config_file(
+        &mut ConfigOptMyStruct::default(),
+        &path,
+        ConfigOptMyStruct::read_config_value(&path),
+        &mut problems,
+    );
+    let in_file = format!(" in file '{}'", path.display());
+    assert_eq!(
+        vec![
+            format!("Unknown config key 'cache'{}", in_file),
+            format!("Unknown config key 'verbose'{}", in_file),
+        ],
+        problems
+    );
+    assert!(matches!(
+        configopt.set("verbose", true),
+        Err(Error::UnknownKey(_))
+    ));
+    assert!(matches!(
+        configopt.set("cache", vec!["b"]),
+        Err(Error::UnknownKey(_))
+    ));
+    configopt.set("token", "changed").unwrap();
+    assert_eq!(
+        Some(String::from("changed")),
+        configopt.get::<String>("token").unwrap()
+    );
+
+    // A `file_only` field is not an argument
+    assert_eq!(
+        Some(String::from("8080")),
+        configopt
+            .arg_to_os_string(&[String::from("port")])
+            .map(|s| s.into_string().unwrap())
+    );
+    assert_eq!(None, configopt.arg_to_os_string(&[String::from("token")]));
+}
+
+#[test]
+fn test_field_sources_conversion() {
+    let mut configopt = ConfigOptMyStruct::default();
+    configopt.port = Some(1);
+    configopt.verbose = true.into();
+    configopt.token = Some(String::from("secret"));
+    configopt.cache = Some(vec![String::from("ignored")]);
+
+    let mut s = MyStruct {
+        port: None,
+        verbose: false,
+        token: None,
+        cache: vec![String::from("kept")],
+        config_files: Vec::new(),
+        generate_config: false,
+        check_config: false,
+        explain_config: false,
+    };
+    s.take(&mut configopt);
+    assert_eq!(Some(1), s.port);
+    assert!(s.verbose);
+    assert_eq!(Some(String::from("secret")), s.token);
+    // A skipped field is never set from the `configopt` type
+    assert_eq!(vec![String::from("kept")], s.cache);
+
+    let configopt = ConfigOptMyStruct::from(s);
+    assert_eq!(None, configopt.cache);
+    let s = MyStruct::try_from(configopt).unwrap();
+    assert!(s.cache.is_empty());
+}
+
+#[test]
+fn test_field_sources_generated_config() {
+    let mut configopt = ConfigOptMyStruct::default();
+    configopt.port = Some(1);
+    configopt.verbose = true.into();
+    let config = configopt.toml_config();
+    assert!(config.contains("port = 1"));
+    assert!(config.contains("### The API token\n# token =\n"));
+    assert!(!config.contains("verbose"));
+    assert!(!config.contains("cache"));
+
+    let schema = ConfigOptMyStruct::json_schema();
+    let properties = schema["properties"].as_object().unwrap();
+    assert!(properties.contains_key("port"));
+    assert_eq!("The API token", properties["token"]["description"]);
+    assert!(!properties.contains_key("verbose"));
+    assert!(!properties.contains_key("cache"));
+}
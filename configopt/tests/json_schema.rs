@@ -0,0 +1,132 @@
+use configopt::ConfigOpt;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use structopt::StructOpt;
+
+/// My application
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug, Serialize), attrs(serde))]
+#[structopt(name = "app")]
+struct MyStruct {
+    /// The port to listen on
+    #[structopt(long, default_value = "8080")]
+    port: u16,
+    /// The host to bind
+    #[structopt(long)]
+    host: String,
+    #[structopt(long, possible_values = &["debug", "info"])]
+    level: Option<String>,
+    #[structopt(long)]
+    names: Vec<String>,
+    #[structopt(flatten)]
+    flat: FlatStruct,
+    #[structopt(subcommand)]
+    cmd: MyEnum,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize)]
+#[configopt(derive(Debug, Serialize), attrs(serde))]
+struct FlatStruct {
+    /// Enable the cache
+    #[structopt(long)]
+    flat_cache: bool,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug, Serialize))]
+enum MyEnum {
+    Cmd1,
+    Cmd2 {
+        #[structopt(long)]
+        field: u32,
+    },
+    Cmd3(AnotherStruct),
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize)]
+#[configopt(derive(Debug, Serialize), attrs(serde))]
+struct AnotherStruct {
+    #[structopt(long)]
+    ratio: Option<f64>,
+}
+
+#[test]
+fn test_json_schema() {
+    let schema = ConfigOptMyStruct::json_schema();
+    assert_eq!(
+        json!("http://json-schema.org/draft-07/schema#"),
+        schema["$schema"]
+    );
+    assert_eq!(json!("app"), schema["title"]);
+    assert_eq!(json!("My application"), schema["description"]);
+    assert_eq!(json!("object"), schema["type"]);
+    // Any key can come from the command line or another config file
+    assert_eq!(None, schema.get("required"));
+
+    let properties = &schema["properties"];
+    assert_eq!(
+        json!({
+            "type": "integer",
+            "description": "The port to listen on",
+            "default": 8080,
+        }),
+        properties["port"]
+    );
+    assert_eq!(
+        json!({
+            "type": "string",
+            "description": "The host to bind",
+        }),
+        properties["host"]
+    );
+    assert_eq!(
+        json!({
+            "type": "string",
+            "enum": ["debug", "info"],
+        }),
+        properties["level"]
+    );
+    assert_eq!(
+        json!({
+            "type": "array",
+            "items": {"type": "string"},
+        }),
+        properties["names"]
+    );
+    // Without `#[serde(flatten)]` a flattened struct is a table of its own in the config file
+    assert_eq!(
+        json!({
+            "type": "boolean",
+            "description": "Enable the cache",
+        }),
+        properties["flat"]["properties"]["flat_cache"]
+    );
+
+    let definitions = &schema["definitions"];
+    assert_eq!(
+        json!({"type": "object", "properties": {}}),
+        definitions["cmd1"]
+    );
+    assert_eq!(
+        json!({
+            "type": "object",
+            "properties": {"field": {"type": "integer"}},
+        }),
+        definitions["cmd2"]
+    );
+    assert_eq!(
+        json!({
+            "type": "object",
+            "properties": {"ratio": {"type": "number"}},
+        }),
+        definitions["cmd3"]
+    );
+
+    // Each subcommand table references its definition
+    for name in &["cmd1", "cmd2", "cmd3"] {
+        assert_eq!(
+            json!({"$ref": format!("#/definitions/{}", name)}),
+            properties[name]
+        );
+    }
+}
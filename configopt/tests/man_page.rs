@@ -0,0 +1,41 @@
+use configopt::ConfigOpt;
+use serde::Serialize;
+use structopt::StructOpt;
+
+/// My application
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(
+    derive(Debug, Serialize),
+    default_config_file("/etc/my-app/config.toml")
+)]
+#[structopt(name = "my-app", version = "1.0.0")]
+struct MyStruct {
+    /// The port to listen on
+    #[structopt(long, short, default_value = "8080", env = "APP_PORT")]
+    port: u16,
+    /// Enable verbose output
+    #[structopt(long)]
+    verbose: bool,
+}
+
+#[test]
+fn test_man_page() {
+    let man_page = ConfigOptMyStruct::man_page();
+    assert!(man_page.starts_with(".TH \"MY\\-APP\" 1 \"\" \"my\\-app 1.0.0\"\n"));
+    assert!(man_page.contains(".SH NAME\nmy\\-app \\- My application\n"));
+    assert!(man_page.contains(".SH SYNOPSIS\n\\fBmy\\-app\\fR [OPTIONS]\n"));
+    assert!(man_page.contains(".SH OPTIONS\n"));
+    assert!(man_page.contains(".TP\n\\fB\\-\\-verbose\\fR\nEnable verbose output\n"));
+    assert!(man_page.contains(
+        ".TP\n\\fB\\-p\\fR, \\fB\\-\\-port\\fR \\fI<port>\\fR\nThe port to listen on\n"
+    ));
+    assert!(man_page.contains(
+        ".SH CONFIGURATION\nEvery key can also be set in a TOML config file.\n.SS my\\-app\n"
+    ));
+    assert!(man_page.contains(
+        ".TP\n\\fBport\\fR = \\fIinteger\\fR\nThe port to listen on\n.br\nFlag: \\fB\\-\\-port, \\-p\\fR\n.br\nEnvironment: \\fBAPP_PORT\\fR\n.br\nDefault: 8080\n.br\n"
+    ));
+    assert!(man_page.ends_with(
+        ".SH FILES\n.TP\n\\fI/etc/my\\-app/config.toml\\fR\nDefault config file\n"
+    ));
+}
@@ -0,0 +1,75 @@
+use configopt::{configopt_fields, ConfigFormat, ConfigOpt, Error, ParseOutcome};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use structopt::{clap::ErrorKind, StructOpt};
+use tempfile::tempdir;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, Serialize), attrs(serde))]
+#[structopt(name = "app", version = "1.2.3")]
+struct MyStruct {
+    #[structopt(long, default_value = "80")]
+    port: u16,
+    #[structopt(long)]
+    host: Option<String>,
+}
+
+#[test]
+fn test_parse_outcome() {
+    assert_eq!(
+        ParseOutcome::Parsed(MyStruct {
+            port: 8080,
+            host: None,
+            config_files: Vec::new(),
+            generate_config: false,
+            check_config: false,
+            explain_config: false,
+        }),
+        MyStruct::try_parse_from(&["app", "--port", "8080"]).unwrap()
+    );
+
+    match MyStruct::try_parse_from(&["app", "--help"]).unwrap() {
+        ParseOutcome::Help(help) => assert!(help.contains("--port <port>")),
+        outcome => panic!("expected help, got {:?}", outcome),
+    }
+
+    assert_eq!(
+        ParseOutcome::Version(String::from("app 1.2.3")),
+        MyStruct::try_parse_from(&["app", "--version"]).unwrap()
+    );
+
+    match MyStruct::try_parse_from(&["app", "--port", "8080", "--generate-config"]).unwrap() {
+        ParseOutcome::GeneratedConfig { format, text } => {
+            assert_eq!(ConfigFormat::Toml, format);
+            assert!(text.contains("port = 8080"));
+        }
+        outcome => panic!("expected a generated config, got {:?}", outcome),
+    }
+
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    fs::write(&path, "port = 8080\nhost = \"localhost\"\n").unwrap();
+    let path = path.to_str().unwrap();
+    assert_eq!(
+        ParseOutcome::Explain(format!(
+            "port = 8080 # from file '{}'\nhost = \"example.com\" # from the command line",
+            path
+        )),
+        MyStruct::try_parse_from(&[
+            "app",
+            "--config-files",
+            path,
+            "--host",
+            "example.com",
+            "--explain-config",
+        ])
+        .unwrap()
+    );
+
+    // Actual failures are still errors
+    match MyStruct::try_parse_from(&["app", "--port", "NaN"]) {
+        Err(Error::Clap(e)) => assert_eq!(ErrorKind::ValueValidation, e.kind),
+        result => panic!("expected a validation error, got {:?}", result),
+    }
+}
@@ -0,0 +1,92 @@
+use configopt::ConfigOpt;
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+/// My application
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug, Serialize), attrs(serde))]
+#[structopt(name = "app")]
+struct MyStruct {
+    /// The port to listen on
+    #[structopt(long, short, default_value = "8080", env = "APP_PORT")]
+    port: u16,
+    #[structopt(long, possible_values = &["debug", "info"])]
+    level: Option<String>,
+    #[structopt(long, hidden = true)]
+    secret: Option<String>,
+    #[structopt(flatten)]
+    server: ServerStruct,
+    #[structopt(subcommand)]
+    cmd: MyEnum,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize)]
+#[configopt(derive(Debug, Serialize), attrs(serde))]
+struct ServerStruct {
+    /// Hosts to bind
+    #[structopt(long)]
+    hosts: Vec<String>,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug, Serialize))]
+enum MyEnum {
+    /// Run the server
+    Serve {
+        #[structopt(long)]
+        verbose: bool,
+    },
+    Stop,
+}
+
+#[test]
+fn test_reference() {
+    let reference = ConfigOptMyStruct::reference();
+    assert_eq!("app", reference.title);
+    assert_eq!("My application", reference.about);
+
+    let sections = reference
+        .sections
+        .iter()
+        .map(|s| (s.command.as_str(), s.name.as_deref()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            ("app", None),
+            ("app", Some("server")),
+            ("app serve", None),
+            ("app stop", None),
+        ],
+        sections
+    );
+
+    let root = &reference.sections[0];
+    let keys = root.entries.iter().map(|e| e.key.as_str()).collect::<Vec<_>>();
+    assert_eq!(vec!["port", "level"], keys);
+    let port = &root.entries[0];
+    assert_eq!("integer", port.ty);
+    assert_eq!(Some("8080"), port.default_value.as_deref());
+    assert_eq!("The port to listen on", port.help);
+    assert_eq!(Some("--port, -p"), port.flag.as_deref());
+    assert_eq!(Some("APP_PORT"), port.env.as_deref());
+    assert_eq!(vec!["debug", "info"], root.entries[1].possible_values);
+
+    let hosts = &reference.sections[1].entries[0];
+    assert_eq!("server.hosts", hosts.key);
+    assert_eq!("array of string", hosts.ty);
+
+    let serve = &reference.sections[2];
+    assert_eq!("Run the server", serve.about);
+    assert_eq!("serve.verbose", serve.entries[0].key);
+    assert_eq!(Some("false"), serve.entries[0].default_value.as_deref());
+
+    let markdown = reference.to_markdown();
+    assert!(markdown.starts_with("# app\n\nMy application\n\n## `app`\n"));
+    assert!(markdown.contains("### `server`\n"));
+    assert!(markdown.contains("## `app serve`\n\nRun the server\n"));
+    assert!(!markdown.contains("app stop"));
+    assert!(!markdown.contains("secret"));
+
+    let html = reference.to_html();
+    assert!(html.contains("<h2><code>app serve</code></h2>"));
+}
@@ -0,0 +1,59 @@
+use configopt::ConfigOpt;
+use serde::Deserialize;
+use std::{convert::TryFrom, fs, path::PathBuf};
+use structopt::StructOpt;
+use tempfile::tempdir;
+
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, PartialEq), attrs(serde))]
+#[serde(deny_unknown_fields)]
+struct MyStruct {
+    #[structopt(long)]
+    #[configopt(relative_to_config)]
+    relative: PathBuf,
+    #[structopt(long)]
+    #[configopt(relative_to_config)]
+    absolute: Option<PathBuf>,
+    #[structopt(long)]
+    #[configopt(relative_to_config, expand_tilde)]
+    home: Option<PathBuf>,
+    #[structopt(long)]
+    #[configopt(relative_to_config)]
+    many: Vec<PathBuf>,
+    #[structopt(long)]
+    untouched: Option<PathBuf>,
+}
+
+#[test]
+fn test_relative_to_config() {
+    let dir = tempdir().unwrap();
+    let config_path = dir.path().join("config.toml");
+    fs::write(
+        &config_path,
+        r#"
+        relative = "data/db"
+        absolute = "/var/lib/db"
+        home = "~/db"
+        many = ["a", "/b"]
+        untouched = "data/db"
+"#,
+    )
+    .unwrap();
+
+    let c = ConfigOptMyStruct::try_from(config_path.as_path()).unwrap();
+    assert_eq!(Some(dir.path().join("data/db")), c.relative);
+    assert_eq!(Some(PathBuf::from("/var/lib/db")), c.absolute);
+    let home = std::env::var_os("HOME").map(|home| PathBuf::from(home).join("db"));
+    assert_eq!(home.or_else(|| Some(dir.path().join("~/db"))), c.home);
+    assert_eq!(
+        Some(vec![dir.path().join("a"), PathBuf::from("/b")]),
+        c.many
+    );
+    assert_eq!(Some(PathBuf::from("data/db")), c.untouched);
+}
+
+#[test]
+fn test_relative_to_config_cli_is_untouched() {
+    let c = ConfigOptMyStruct::from_iter_safe(&["app", "--relative", "data/db"]).unwrap();
+    assert_eq!(Some(PathBuf::from("data/db")), c.relative);
+}
@@ -0,0 +1,82 @@
+use configopt::{configopt_fields, ConfigOpt, Error};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use structopt::{clap::ErrorKind, StructOpt};
+use tempfile::tempdir;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, Serialize), attrs(serde))]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long, default_value = "80")]
+    port: u16,
+    #[structopt(long)]
+    verbose: bool,
+    name: String,
+}
+
+#[test]
+fn test_required_from_config() {
+    let dir = tempdir().unwrap();
+    let config_path = dir.path().join("config.toml");
+    fs::write(
+        &config_path,
+        r#"
+        host = "localhost"
+        verbose = true
+        name = "from_config"
+"#,
+    )
+    .unwrap();
+    let config_path = config_path.to_str().unwrap();
+
+    // Every required argument is set in the config file
+    let s = MyStruct::try_from_iter_with_configopt(&["app", "--config-files", config_path])
+        .unwrap();
+    assert_eq!(
+        MyStruct {
+            host: String::from("localhost"),
+            port: 80,
+            verbose: true,
+            name: String::from("from_config"),
+            config_files: vec![config_path.into()],
+            generate_config: false,
+            check_config: false,
+            explain_config: false,
+        },
+        s
+    );
+
+    // The command line takes precedence over the config file
+    let s = MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--config-files",
+        config_path,
+        "--host",
+        "example.com",
+        "from_cli",
+    ])
+    .unwrap();
+    assert_eq!("example.com", s.host);
+    assert_eq!("from_cli", s.name);
+
+    // A required argument set nowhere is still an error
+    match MyStruct::try_from_iter_with_configopt(&["app", "--host", "example.com"]) {
+        Err(Error::Clap(e)) => assert_eq!(ErrorKind::MissingRequiredArgument, e.kind),
+        result => panic!("expected a missing argument error, got {:?}", result),
+    }
+
+    // Other errors are not masked by the config file
+    match MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--config-files",
+        config_path,
+        "--port",
+        "NaN",
+    ]) {
+        Err(Error::Clap(e)) => assert_eq!(ErrorKind::ValueValidation, e.kind),
+        result => panic!("expected a validation error, got {:?}", result),
+    }
+}
@@ -0,0 +1,40 @@
+use configopt::{ConfigOptArgToOsString, DefaultValueStore};
+use std::ffi::OsString;
+use structopt::clap::{App, Arg};
+
+struct Defaults(u32);
+
+impl ConfigOptArgToOsString for Defaults {
+    fn arg_to_os_string(&self, arg_path: &[String]) -> Option<OsString> {
+        match arg_path {
+            [arg] if arg == "port" => Some(self.0.to_string().into()),
+            _ => None,
+        }
+    }
+}
+
+fn app<'a>(defaults: &Defaults, store: &'a DefaultValueStore) -> App<'a, 'a> {
+    let port = Arg::with_name("port")
+        .long("port")
+        .takes_value(true)
+        .required(true);
+    App::new("app").arg(store.default_value(port, &[String::from("port")], defaults))
+}
+
+#[test]
+fn test_set_defaults() {
+    // Each app only borrows its own store so repeatedly building apps does not accumulate values
+    for port in 0..3 {
+        let store = DefaultValueStore::new();
+        let matches = app(&Defaults(port), &store)
+            .get_matches_from_safe(["app"])
+            .unwrap();
+        assert_eq!(Some(port.to_string().as_str()), matches.value_of("port"));
+    }
+
+    let store = DefaultValueStore::default();
+    let matches = app(&Defaults(8080), &store)
+        .get_matches_from_safe(["app", "--port", "80"])
+        .unwrap();
+    assert_eq!(Some("80"), matches.value_of("port"));
+}
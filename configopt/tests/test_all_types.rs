@@ -113,6 +113,8 @@ fn test_configopt_generate_config() {
             cmd: None,
             config_files: None,
             generate_config: Some(true).into(),
+            check_config: None.into(),
+            explain_config: None.into(),
         },
         ConfigOptMyStruct::from_iter_safe(&["app", "--generate-config"]).unwrap()
     );
@@ -134,10 +136,14 @@ fn test_configopt_generate_config() {
                     flat_numbers: None,
                 },
                 config_files: None,
-                generate_config: Some(true).into()
+                generate_config: Some(true).into(),
+                check_config: None.into(),
+                explain_config: None.into()
             })),
             config_files: None,
             generate_config: None.into(),
+            check_config: None.into(),
+            explain_config: None.into(),
         },
         ConfigOptMyStruct::from_iter_safe(&["app", "cmd3", "--generate-config"]).unwrap()
     );
@@ -159,10 +165,14 @@ fn test_configopt_generate_config() {
                     flat_numbers: None,
                 },
                 config_files: None,
-                generate_config: Some(true).into()
+                generate_config: Some(true).into(),
+                check_config: None.into(),
+                explain_config: None.into()
             })),
             config_files: None,
             generate_config: Some(true).into(),
+            check_config: None.into(),
+            explain_config: None.into(),
         },
         ConfigOptMyStruct::from_iter_safe(&[
             "app",
@@ -190,10 +200,14 @@ fn test_configopt_generate_config() {
                     flat_numbers: None,
                 },
                 config_files: None,
-                generate_config: None.into()
+                generate_config: None.into(),
+                check_config: None.into(),
+                explain_config: None.into()
             })),
             config_files: None,
             generate_config: Some(true).into(),
+            check_config: None.into(),
+            explain_config: None.into(),
         },
         ConfigOptMyStruct::from_iter_safe(&["app", "--generate-config", "cmd3",]).unwrap()
     );
@@ -212,7 +226,9 @@ fn test_configopt_from_cli_no_args() {
             path: None,
             cmd: None,
             config_files: None,
-            generate_config: None.into()
+            generate_config: None.into(),
+            check_config: None.into(),
+            explain_config: None.into()
         },
         ConfigOptMyStruct::from_iter_safe(&["app"]).unwrap()
     );
@@ -227,7 +243,9 @@ fn test_configopt_from_cli_no_args() {
             path: None,
             cmd: Some(ConfigOptMyEnum::Cmd1),
             config_files: None,
-            generate_config: None.into()
+            generate_config: None.into(),
+            check_config: None.into(),
+            explain_config: None.into()
         },
         ConfigOptMyStruct::from_iter_safe(&["app", "cmd1"]).unwrap()
     );
@@ -245,7 +263,9 @@ fn test_configopt_from_cli_no_args() {
                 field_2: None,
             }),
             config_files: None,
-            generate_config: None.into()
+            generate_config: None.into(),
+            check_config: None.into(),
+            explain_config: None.into()
         },
         ConfigOptMyStruct::from_iter_safe(&["app", "cmd2"]).unwrap()
     );
@@ -267,10 +287,14 @@ fn test_configopt_from_cli_no_args() {
                     flat_numbers: None,
                 },
                 config_files: None,
-                generate_config: None.into()
+                generate_config: None.into(),
+                check_config: None.into(),
+                explain_config: None.into()
             })),
             config_files: None,
-            generate_config: None.into()
+            generate_config: None.into(),
+            check_config: None.into(),
+            explain_config: None.into()
         },
         ConfigOptMyStruct::from_iter_safe(&["app", "cmd3"]).unwrap()
     );
@@ -293,7 +317,9 @@ fn test_configopt_from_cli_all_args() {
             path: Some(PathBuf::from("/some/path")),
             cmd: Some(ConfigOptMyEnum::Cmd1),
             config_files: None,
-            generate_config: None.into()
+            generate_config: None.into(),
+            check_config: None.into(),
+            explain_config: None.into()
         },
         ConfigOptMyStruct::from_iter_safe(cli).unwrap()
     );
@@ -315,7 +341,9 @@ fn test_configopt_from_cli_all_args() {
                 field_2: Some(String::from("from_cli4"))
             }),
             config_files: None,
-            generate_config: None.into()
+            generate_config: None.into(),
+            check_config: None.into(),
+            explain_config: None.into()
         },
         ConfigOptMyStruct::from_iter_safe(cli).unwrap()
     );
@@ -341,10 +369,14 @@ fn test_configopt_from_cli_all_args() {
                     flat_numbers: Some(vec![7]),
                 },
                 config_files: None,
-                generate_config: None.into()
+                generate_config: None.into(),
+                check_config: None.into(),
+                explain_config: None.into()
             })),
             config_files: None,
-            generate_config: None.into()
+            generate_config: None.into(),
+            check_config: None.into(),
+            explain_config: None.into()
         },
         ConfigOptMyStruct::from_iter_safe(cli).unwrap()
     );
@@ -481,10 +513,14 @@ fn test_take_for() {
         cmd: None,
         config_files: None,
         generate_config: None.into(),
+        check_config: None.into(),
+        explain_config: None.into(),
     };
     let mut s =
         MyStruct::try_from_iter_with_defaults(&["app", "cmd3", "--field-a=from_cli"], &c).unwrap();
-    assert!(s.optional.is_none());
+    // Optional arguments get their default value too
+    assert_eq!(Some(String::from("configopt_optional")), s.optional);
+    s.optional = None;
     c.take_for(&mut s);
     assert_eq!(Some(String::from("configopt_optional")), s.optional);
 }
@@ -504,6 +540,8 @@ fn test_patch_for() {
         cmd: None,
         config_files: None,
         generate_config: None.into(),
+        check_config: None.into(),
+        explain_config: None.into(),
     };
 
     let mut s = MyStruct::try_from_iter_with_defaults(
@@ -531,7 +569,8 @@ fn test_patch_for() {
         &c,
     )
     .unwrap();
-    assert!(s.optional.is_none());
+    assert_eq!(Some(String::from("optional_from_configopt")), s.optional);
+    s.optional = None;
     c.patch_for(&mut s);
     assert_eq!(Some(String::from("optional_from_configopt")), s.optional);
 }
@@ -549,6 +588,8 @@ fn test_from() {
         cmd: MyEnum::Cmd1,
         config_files: Vec::new(),
         generate_config: false.into(),
+        check_config: false.into(),
+        explain_config: false.into(),
     };
     let c1 = ConfigOptMyStruct::from(m);
     let c2 = ConfigOptMyStruct {
@@ -562,6 +603,8 @@ fn test_from() {
         cmd: Some(ConfigOptMyEnum::Cmd1),
         config_files: Some(Vec::new()),
         generate_config: Some(false).into(),
+        check_config: Some(false).into(),
+        explain_config: Some(false).into(),
     };
     assert_eq!(c1, c2);
 
@@ -576,6 +619,8 @@ fn test_from() {
         flat_struct: f,
         config_files: Vec::new(),
         generate_config: false,
+        check_config: false,
+        explain_config: false,
     };
     let f = MyStruct {
         maybe: true,
@@ -588,6 +633,8 @@ fn test_from() {
         cmd: MyEnum::Cmd3(a),
         config_files: Vec::new(),
         generate_config: false,
+        check_config: false,
+        explain_config: false,
     };
     let c1 = ConfigOptMyStruct::from(f);
     let f = ConfigOptFlatStruct {
@@ -601,6 +648,8 @@ fn test_from() {
         flat_struct: f,
         config_files: Some(Vec::new()),
         generate_config: Some(false).into(),
+        check_config: Some(false).into(),
+        explain_config: Some(false).into(),
     };
     let c2 = ConfigOptMyStruct {
         maybe: Some(true).into(),
@@ -613,6 +662,8 @@ fn test_from() {
         cmd: Some(ConfigOptMyEnum::Cmd3(a)),
         config_files: Some(Vec::new()),
         generate_config: Some(false).into(),
+        check_config: Some(false).into(),
+        explain_config: Some(false).into(),
     };
     assert_eq!(c1, c2);
 }
@@ -632,6 +683,8 @@ fn test_try_from() {
         cmd: Some(ConfigOptMyEnum::Cmd1),
         config_files: None,
         generate_config: None.into(),
+        check_config: None.into(),
+        explain_config: None.into(),
     };
     assert!(!c.is_convertible());
     c.not_optional = Some(String::from("testing123"));
@@ -651,6 +704,8 @@ fn test_try_from() {
         cmd: MyEnum::Cmd1,
         config_files: Vec::new(),
         generate_config: false,
+        check_config: false,
+        explain_config: false,
     };
     assert_eq!(m1, m2);
 
@@ -665,6 +720,8 @@ fn test_try_from() {
         flat_struct: f,
         config_files: None,
         generate_config: Some(false).into(),
+        check_config: Some(false).into(),
+        explain_config: Some(false).into(),
     };
     let mut c = ConfigOptMyStruct {
         maybe: Some(true).into(),
@@ -677,6 +734,8 @@ fn test_try_from() {
         cmd: Some(ConfigOptMyEnum::Cmd3(a)),
         config_files: None,
         generate_config: None.into(),
+        check_config: None.into(),
+        explain_config: None.into(),
     };
     assert!(!c.is_convertible());
     let f = ConfigOptFlatStruct {
@@ -690,6 +749,8 @@ fn test_try_from() {
         flat_struct: f,
         config_files: None,
         generate_config: Some(false).into(),
+        check_config: Some(false).into(),
+        explain_config: Some(false).into(),
     };
     c.cmd = Some(ConfigOptMyEnum::Cmd3(a));
     assert!(c.is_convertible());
@@ -705,6 +766,8 @@ fn test_try_from() {
         flat_struct: f,
         config_files: Vec::new(),
         generate_config: false,
+        check_config: false,
+        explain_config: false,
     };
     let m2 = MyStruct {
         maybe: true,
@@ -717,6 +780,8 @@ fn test_try_from() {
         cmd: MyEnum::Cmd3(a),
         config_files: Vec::new(),
         generate_config: false,
+        check_config: false,
+        explain_config: false,
     };
     assert_eq!(m1, m2);
 }
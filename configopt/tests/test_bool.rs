@@ -9,14 +9,12 @@ fn test_configopt_with_optional_bool() {
     #[configopt(derive(Debug, PartialEq), attrs(serde))]
     #[serde(deny_unknown_fields)]
     struct MyStruct {
-        #[structopt(long, default_value = "true")]
+        #[structopt(long)]
         maybe: Option<bool>,
     }
 
     let c = ConfigOptMyStruct::from_iter_safe(&["app"]).unwrap();
     assert_eq!(None, c.maybe);
-    let c = ConfigOptMyStruct::from_iter_safe(&["app", "--maybe"]).unwrap();
-    assert_eq!(Some(true), c.maybe);
     let c = ConfigOptMyStruct::from_iter_safe(&["app", "--maybe=false"]).unwrap();
     assert_eq!(Some(false), c.maybe);
     let c = ConfigOptMyStruct::from_iter_safe(&["app", "--maybe=true"]).unwrap();
@@ -26,26 +24,26 @@ fn test_configopt_with_optional_bool() {
         maybe: Some(true),
         config_files: None,
         generate_config: None.into(),
+        check_config: None.into(),
+        explain_config: None.into(),
     };
     let s = MyStruct::try_from_iter_with_defaults(&["app"], &c).unwrap();
-    assert_eq!(None, s.maybe);
-    let s = MyStruct::try_from_iter_with_defaults(&["app", "--maybe"], &c).unwrap();
     assert_eq!(Some(true), s.maybe);
     let s = MyStruct::try_from_iter_with_defaults(&["app", "--maybe=true"], &c).unwrap();
     assert_eq!(Some(true), s.maybe);
     let s = MyStruct::try_from_iter_with_defaults(&["app", "--maybe=false"], &c).unwrap();
     assert_eq!(Some(false), s.maybe);
+    // The option takes a value
+    assert!(MyStruct::try_from_iter_with_defaults(&["app", "--maybe"], &c).is_err());
 
     let c = ConfigOptMyStruct {
         maybe: Some(false),
         config_files: None,
         generate_config: None.into(),
+        check_config: None.into(),
+        explain_config: None.into(),
     };
     let s = MyStruct::try_from_iter_with_defaults(&["app"], &c).unwrap();
-    assert_eq!(None, s.maybe);
-    // TODO: This is kinda odd. Because we set the default to `false`, passing the flag does not
-    // make the value `true`. We could probably use a `from_occurrences` parser to get around this.
-    let s = MyStruct::try_from_iter_with_defaults(&["app", "--maybe"], &c).unwrap();
     assert_eq!(Some(false), s.maybe);
     let s = MyStruct::try_from_iter_with_defaults(&["app", "--maybe=true"], &c).unwrap();
     assert_eq!(Some(true), s.maybe);
@@ -73,6 +71,8 @@ fn test_configopt_with_bool() {
         maybe: Some(true).into(),
         config_files: None,
         generate_config: None.into(),
+        check_config: None.into(),
+        explain_config: None.into(),
     };
     let s = MyStruct::try_from_iter_with_defaults(&["app"], &c).unwrap();
     // We want this to be true, but setting a default value for a boolean is impossible.
@@ -84,6 +84,8 @@ fn test_configopt_with_bool() {
         maybe: Some(false).into(),
         config_files: None,
         generate_config: None.into(),
+        check_config: None.into(),
+        explain_config: None.into(),
     };
     let s = MyStruct::try_from_iter_with_defaults(&["app"], &c).unwrap();
     assert_eq!(false, s.maybe);
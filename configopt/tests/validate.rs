@@ -0,0 +1,134 @@
+use configopt::{
+    configopt_fields,
+    validate::{ConfigLayer, ValidationError},
+    ConfigOpt, ConfigOptType, Error,
+};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+fn even(value: &u32) -> Result<(), String> {
+    if value % 2 == 0 {
+        Ok(())
+    } else {
+        Err(format!("{} is odd", value))
+    }
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, Serialize), attrs(serde))]
+#[structopt(name = "app")]
+struct MyStruct {
+    #[structopt(long, default_value = "80")]
+    #[configopt(validate(range(1..=65535)))]
+    port: u32,
+    #[structopt(long)]
+    #[configopt(validate(regex = "debug|info|warn|error"))]
+    log_level: Option<String>,
+    #[structopt(long)]
+    #[configopt(validate(non_empty))]
+    hosts: Vec<String>,
+    #[structopt(long)]
+    #[configopt(validate(path_exists))]
+    data_dir: Option<PathBuf>,
+    #[structopt(flatten)]
+    server: Server,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, Serialize), attrs(serde))]
+struct Server {
+    #[structopt(long)]
+    #[configopt(validate(range(1..), with = even))]
+    workers: Option<u32>,
+}
+
+fn failures(configopt: &ConfigOptMyStruct) -> Vec<(String, String)> {
+    let mut failures = Vec::new();
+    configopt.validate_with_prefix(&[], &mut failures);
+    failures
+}
+
+#[test]
+fn test_validate() {
+    let mut configopt = ConfigOptMyStruct::default();
+    // Unset values are not checked
+    assert!(failures(&configopt).is_empty());
+    assert!(configopt.validate(None, &[]).is_ok());
+
+    configopt.port = Some(8080);
+    configopt.log_level = Some(String::from("info"));
+    configopt.hosts = Some(vec![String::from("localhost")]);
+    configopt.data_dir = Some(PathBuf::from("."));
+    configopt.server.workers = Some(4);
+    assert!(failures(&configopt).is_empty());
+
+    configopt.port = Some(0);
+    configopt.log_level = Some(String::from("verbose"));
+    configopt.hosts = Some(Vec::new());
+    configopt.data_dir = Some(PathBuf::from("does/not/exist"));
+    configopt.server.workers = Some(0);
+    assert_eq!(
+        vec![
+            (
+                String::from("port"),
+                String::from("0 is not in the range 1..=65535")
+            ),
+            (
+                String::from("log_level"),
+                String::from("'verbose' does not match 'debug|info|warn|error'")
+            ),
+            (String::from("hosts"), String::from("the value is empty")),
+            (
+                String::from("data_dir"),
+                String::from("'does/not/exist' does not exist")
+            ),
+            (
+                String::from("server.workers"),
+                String::from("0 is not in the range 1..")
+            ),
+        ],
+        failures(&configopt)
+    );
+
+    configopt.port = Some(80);
+    configopt.log_level = None;
+    configopt.hosts = None;
+    configopt.data_dir = None;
+    configopt.server.workers = Some(3);
+    let from_cli = ConfigOptMyStruct {
+        server: ConfigOptServer { workers: Some(3) },
+        ..ConfigOptMyStruct::default()
+    };
+    match configopt.validate(Some(&from_cli), &[]) {
+        Err(Error::Validation(errors)) => assert_eq!(
+            vec![ValidationError {
+                key: String::from("server.workers"),
+                layer: ConfigLayer::CommandLine,
+                message: String::from("3 is odd"),
+            }],
+            errors
+        ),
+        result => panic!("expected a validation error, got {:?}", result),
+    }
+    match configopt.validate(None, &[]) {
+        Err(Error::Validation(errors)) => assert_eq!(
+            "Invalid value for config key 'server.workers' from the default value, err: 3 is odd",
+            errors[0].to_string()
+        ),
+        result => panic!("expected a validation error, got {:?}", result),
+    }
+}
+
+#[test]
+fn test_validate_parse() {
+    match MyStruct::try_from_iter_with_configopt(&["app", "--port", "0"]) {
+        Err(Error::Validation(errors)) => {
+            assert_eq!(1, errors.len());
+            assert_eq!("port", errors[0].key);
+            assert_eq!(ConfigLayer::CommandLine, errors[0].layer);
+        }
+        result => panic!("expected a validation error, got {:?}", result),
+    }
+}
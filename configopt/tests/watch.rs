@@ -0,0 +1,84 @@
+#![cfg(feature = "watch")]
+
+use configopt::{
+    watch::{self, WatchOptions},
+    ConfigOpt,
+};
+use serde::Deserialize;
+use std::{
+    fs,
+    path::Path,
+    sync::mpsc::Receiver,
+    time::{Duration, Instant},
+};
+use structopt::StructOpt;
+use tempfile::tempdir;
+
+#[configopt::configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize, PartialEq)]
+#[configopt(derive(Debug, PartialEq), attrs(serde))]
+#[serde(deny_unknown_fields)]
+struct MyStruct {
+    #[structopt(long)]
+    port: u16,
+    #[structopt(long)]
+    host: Option<String>,
+}
+
+// Keep writing `contents` until a reload matching `done` is received. A single write may not
+// change the modification time on file systems with coarse timestamps.
+fn write_until<T>(
+    path: &Path,
+    contents: &str,
+    receiver: &Receiver<configopt::Result<T>>,
+    done: impl Fn(&configopt::Result<T>) -> bool,
+) -> configopt::Result<T> {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < deadline {
+        fs::write(path, contents).unwrap();
+        if let Ok(result) = receiver.recv_timeout(Duration::from_millis(100)) {
+            if done(&result) {
+                return result;
+            }
+        }
+    }
+    panic!("the watcher did not reload '{}' in time", path.display());
+}
+
+#[test]
+fn test_watch_reloads_config() {
+    let dir = tempdir().unwrap();
+    let config_path = dir.path().join("config.toml");
+    fs::write(&config_path, "port = 8080\nhost = \"from_file\"\n").unwrap();
+    let config_arg = format!("--config-files={}", config_path.to_string_lossy());
+
+    let options = WatchOptions {
+        poll_interval: Duration::from_millis(10),
+        debounce: Duration::from_millis(50),
+    };
+    let (watcher, receiver) =
+        watch::watch_channel::<MyStruct, _>(&["app", "--host=from_cli", &config_arg], options)
+            .unwrap();
+    assert_eq!(vec![config_path.clone()], watcher.paths());
+
+    let s = write_until(
+        &config_path,
+        "port = 9090\nhost = \"from_file\"\n",
+        &receiver,
+        |r| matches!(r, Ok(s) if s.port == 9090),
+    )
+    .unwrap();
+    // CLI arguments stay the top layer
+    assert_eq!(Some(String::from("from_cli")), s.host);
+
+    // A config file that fails to parse is sent as an error
+    write_until(
+        &config_path,
+        "port = \"not a port\"\n",
+        &receiver,
+        Result::is_err,
+    )
+    .unwrap_err();
+
+    watcher.stop();
+}